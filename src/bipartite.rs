@@ -0,0 +1,146 @@
+use std::collections::VecDeque;
+
+use num::cast::AsPrimitive;
+use num::traits::PrimInt;
+use crate::traits::Graph;
+
+/// The outcome of a bipartiteness test.
+pub enum Bipartition<V> {
+    /// The side of every vertex, such that every edge joins both sides.
+    Coloring(Vec<bool>),
+    /// An odd cycle proving that the graph is not bipartite, as its vertices
+    /// in order around the cycle. A self-loop gives a single vertex.
+    OddCycle(Vec<V>),
+}
+
+/// Two-colors an undirected graph by breadth-first search from the lowest
+/// uncolored vertex of every component. Isolated vertices and the roots of
+/// components are put on the `false` side.
+pub fn bipartition<V>(g: &impl Graph<V>) -> Bipartition<V>
+where
+    V: PrimInt + AsPrimitive<usize>,
+{
+    let n = g.nv().as_();
+    let mut colors = vec![false; n];
+    let mut parents = vec![V::max_value(); n];
+    let mut visited = vec![false; n];
+    let mut queue: VecDeque<V> = VecDeque::new();
+    for root in g.vertices() {
+        if visited[root.as_()] {
+            continue;
+        }
+        visited[root.as_()] = true;
+        queue.push_back(root);
+        while let Some(u) = queue.pop_front() {
+            let uu = u.as_();
+            for v in g.out_neighbors(u) {
+                let vu = v.as_();
+                if !visited[vu] {
+                    visited[vu] = true;
+                    colors[vu] = !colors[uu];
+                    parents[vu] = u;
+                    queue.push_back(*v);
+                } else if colors[vu] == colors[uu] {
+                    return Bipartition::OddCycle(odd_cycle(&parents, u, *v));
+                }
+            }
+        }
+    }
+    Bipartition::Coloring(colors)
+}
+
+// the cycle closed by the edge (u, v) between two vertices of the same BFS
+// level: the tree paths from u and v up to their lowest common ancestor.
+fn odd_cycle<V>(parents: &[V], u: V, v: V) -> Vec<V>
+where
+    V: PrimInt + AsPrimitive<usize>,
+{
+    if u == v {
+        return vec![u];
+    }
+    let mut left = vec![u];
+    let mut right = vec![v];
+    let (mut a, mut b) = (u, v);
+    while a != b {
+        a = parents[a.as_()];
+        b = parents[b.as_()];
+        left.push(a);
+        right.push(b);
+    }
+    // both paths end at the common ancestor.
+    right.pop();
+    left.extend(right.into_iter().rev());
+    left
+}
+
+pub fn is_bipartite<V>(g: &impl Graph<V>) -> bool
+where
+    V: PrimInt + AsPrimitive<usize>,
+{
+    match bipartition(g) {
+        Bipartition::Coloring(_) => true,
+        Bipartition::OddCycle(_) => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::random_graph;
+    use crate::{StaticGraph, Vertex};
+
+    fn check(g: &StaticGraph) -> bool {
+        match bipartition(g) {
+            Bipartition::Coloring(sides) => {
+                assert!(g.vertices().all(|u| g.out_neighbors(u).iter().all(|v| sides[u as usize] != sides[*v as usize])));
+                true
+            }
+            Bipartition::OddCycle(cycle) => {
+                // a closed walk of odd length through distinct vertices.
+                assert_eq!(cycle.len() % 2, 1);
+                let mut distinct = cycle.clone();
+                distinct.sort_unstable();
+                distinct.dedup();
+                assert_eq!(distinct.len(), cycle.len());
+                for (i, u) in cycle.iter().enumerate() {
+                    assert!(g.has_edge(*u, cycle[(i + 1) % cycle.len()]));
+                }
+                false
+            }
+        }
+    }
+
+    #[test]
+    fn fixed_graphs() {
+        let even = StaticGraph::from_edges(7, (0..6).map(|v| (v, (v + 1) % 6)).collect());
+        assert!(check(&even) && is_bipartite(&even));
+        let odd = StaticGraph::from_edges(7, (0..7).map(|v| (v, (v + 1) % 7)).collect());
+        assert!(!check(&odd) && !is_bipartite(&odd));
+        // a triangle hanging off a long path.
+        let mut edges: Vec<(Vertex, Vertex)> = (1..10).map(|v| (v - 1, v)).collect();
+        edges.extend(vec![(9, 10), (10, 11), (11, 9)]);
+        match bipartition(&StaticGraph::from_edges(12, edges)) {
+            Bipartition::OddCycle(mut cycle) => {
+                cycle.sort_unstable();
+                assert_eq!(cycle, vec![9, 10, 11]);
+            }
+            Bipartition::Coloring(_) => panic!("a triangle is not bipartite"),
+        }
+        match bipartition(&StaticGraph::from_edges(3, vec![(0, 1), (2, 2)])) {
+            Bipartition::OddCycle(cycle) => assert_eq!(cycle, vec![2]),
+            Bipartition::Coloring(_) => panic!("a self-loop is not bipartite"),
+        }
+        assert!(check(&StaticGraph::from_edges(0, vec![])));
+    }
+
+    #[test]
+    fn random_graphs() {
+        let mut nbipartite = 0;
+        for seed in 0..100 {
+            if check(&random_graph(40, 20 + seed as usize % 25, seed)) {
+                nbipartite += 1;
+            }
+        }
+        assert!(nbipartite > 0 && nbipartite < 100);
+    }
+}
@@ -0,0 +1,568 @@
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use rayon::prelude::*;
+
+use num::cast::AsPrimitive;
+use num::traits::PrimInt;
+use crate::traits::Graph;
+use crate::traversals::{all_sources_bfs_map, bfs, dijkstra_shortest_paths};
+
+/// Where the score of vertices without out-edges goes at each iteration.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Dangling {
+    /// Spread uniformly over all vertices.
+    Uniform,
+    /// Spread according to the personalization vector.
+    Personalization,
+    /// Kept by the dangling vertex, as if it had a self-loop.
+    SelfLoop,
+}
+
+#[derive(Clone, Debug)]
+pub struct PageRankConfig {
+    /// Probability of following an edge rather than teleporting.
+    pub damping: f64,
+    /// Iteration stops once the L1 change of the scores is below this.
+    pub tolerance: f64,
+    pub max_iterations: usize,
+    pub dangling: Dangling,
+    /// Teleport distribution over the vertices; uniform if `None`. It does not
+    /// need to be normalized.
+    pub personalization: Option<Vec<f64>>,
+}
+
+impl Default for PageRankConfig {
+    fn default() -> Self {
+        PageRankConfig {
+            damping: 0.85,
+            tolerance: 1e-6,
+            max_iterations: 100,
+            dangling: Dangling::Uniform,
+            personalization: None,
+        }
+    }
+}
+
+/// Pull-based parallel PageRank. Returns the scores, which sum to one, and the
+/// number of iterations performed.
+pub fn pagerank<V, G>(g: &G, config: &PageRankConfig) -> (Vec<f64>, usize)
+where
+    G: Graph<V> + Sync,
+    V: PrimInt + AsPrimitive<usize> + Send + Sync,
+    usize: AsPrimitive<V>,
+{
+    let n = g.nv().as_();
+    if n == 0 {
+        return (vec![], 0);
+    }
+    let d = config.damping;
+    assert!((0.0..=1.0).contains(&d), "damping must be in [0, 1]");
+
+    let teleport: Vec<f64> = match &config.personalization {
+        Some(p) => {
+            assert_eq!(p.len(), n, "personalization must have one entry per vertex");
+            assert!(p.iter().all(|x| *x >= 0.0), "personalization must be non-negative");
+            let total: f64 = p.iter().sum();
+            assert!(total > 0.0, "personalization must not be all zeros");
+            p.iter().map(|x| x / total).collect()
+        }
+        None => vec![1.0 / n as f64; n],
+    };
+    let outdeg: Vec<usize> = (0..n).into_par_iter().map(|u| g.out_degree(u.as_()).as_()).collect();
+
+    let mut scores = teleport.clone();
+    let mut contrib = vec![0.0f64; n];
+    let mut next = vec![0.0f64; n];
+    let mut iterations = 0;
+    while iterations < config.max_iterations {
+        iterations += 1;
+        contrib.par_iter_mut().enumerate().for_each(|(u, c)| {
+            *c = if outdeg[u] > 0 { scores[u] / outdeg[u] as f64 } else { 0.0 };
+        });
+        let dangling_sum: f64 = (0..n).into_par_iter().filter(|u| outdeg[*u] == 0).map(|u| scores[u]).sum();
+
+        let contrib = &contrib;
+        let scores_ref = &scores;
+        let teleport_ref = &teleport;
+        next.par_iter_mut().enumerate().for_each(|(v, x)| {
+            let pulled: f64 = g.in_neighbors(v.as_()).iter().map(|u| contrib[u.as_()]).sum();
+            let dangling = match config.dangling {
+                Dangling::Uniform => dangling_sum / n as f64,
+                Dangling::Personalization => dangling_sum * teleport_ref[v],
+                Dangling::SelfLoop => if outdeg[v] == 0 { scores_ref[v] } else { 0.0 },
+            };
+            *x = (1.0 - d) * teleport_ref[v] + d * (pulled + dangling);
+        });
+
+        let err: f64 = scores.par_iter().zip(next.par_iter()).map(|(a, b)| (a - b).abs()).sum();
+        std::mem::swap(&mut scores, &mut next);
+        if err < config.tolerance {
+            break;
+        }
+    }
+    (scores, iterations)
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct BetweennessConfig {
+    /// Divide by the number of ordered pairs of other vertices, (n-1)(n-2).
+    pub normalized: bool,
+    /// Use this many random sources instead of all vertices, and scale the
+    /// result up accordingly.
+    pub samples: Option<usize>,
+    /// Seed for picking the sampled sources.
+    pub seed: u64,
+}
+
+// runs `dependencies` from every source (or the sampled ones) in parallel and
+// sums the results, each thread accumulating into its own vector.
+fn brandes<V, F>(n: usize, config: &BetweennessConfig, dependencies: F) -> Vec<f64>
+where
+    V: PrimInt + AsPrimitive<usize> + Send + Sync,
+    usize: AsPrimitive<V>,
+    F: Fn(V, &mut [f64]) + Sync + Send,
+{
+    let sources: Vec<usize> = match config.samples {
+        Some(k) if k < n => {
+            let mut rng = StdRng::seed_from_u64(config.seed);
+            rand::seq::index::sample(&mut rng, n, k).into_vec()
+        }
+        _ => (0..n).collect(),
+    };
+    let mut bc = sources
+        .par_iter()
+        .fold(|| vec![0.0f64; n], |mut acc, s| {
+            dependencies(s.as_(), &mut acc);
+            acc
+        })
+        .reduce(|| vec![0.0f64; n], |mut a, b| {
+            a.iter_mut().zip(b.iter()).for_each(|(x, y)| *x += y);
+            a
+        });
+
+    let mut scale = 1.0;
+    if !sources.is_empty() && sources.len() < n {
+        scale *= n as f64 / sources.len() as f64;
+    }
+    if config.normalized && n > 2 {
+        scale /= ((n - 1) * (n - 2)) as f64;
+    }
+    if scale != 1.0 {
+        bc.iter_mut().for_each(|x| *x *= scale);
+    }
+    bc
+}
+
+/// Brandes' betweenness centrality of an unweighted graph, parallelized over
+/// the sources. Paths are counted between ordered pairs of vertices, so for
+/// undirected graphs unnormalized values are twice the usual ones.
+pub fn betweenness_centrality<V, G>(g: &G, config: &BetweennessConfig) -> Vec<f64>
+where
+    G: Graph<V> + Sync,
+    V: PrimInt + AsPrimitive<usize> + Send + Sync,
+    usize: AsPrimitive<V>,
+{
+    let n = g.nv().as_();
+    brandes(n, config, |s: V, bc: &mut [f64]| {
+        let levels = bfs(g, s);
+        let unreached = V::max_value();
+
+        // reached vertices sorted by level.
+        let mut counts: Vec<usize> = Vec::new();
+        for l in levels.iter().filter(|l| **l != unreached) {
+            let lu = l.as_();
+            if lu >= counts.len() {
+                counts.resize(lu + 1, 0);
+            }
+            counts[lu] += 1;
+        }
+        let mut starts = vec![0usize; counts.len()];
+        for l in 1..counts.len() {
+            starts[l] = starts[l - 1] + counts[l - 1];
+        }
+        let mut order: Vec<V> = vec![V::zero(); counts.iter().sum()];
+        for (v, l) in levels.iter().enumerate().filter(|(_, l)| **l != unreached) {
+            let lu = l.as_();
+            order[starts[lu]] = v.as_();
+            starts[lu] += 1;
+        }
+
+        let is_pred = |v: V, w: V| {
+            let lv = levels[v.as_()];
+            lv != unreached && lv + V::one() == levels[w.as_()]
+        };
+        let mut sigma = vec![0.0f64; n];
+        sigma[s.as_()] = 1.0;
+        for w in order.iter().skip(1) {
+            sigma[w.as_()] = g.in_neighbors(*w).iter().filter(|v| is_pred(**v, *w)).map(|v| sigma[v.as_()]).sum();
+        }
+        let mut delta = vec![0.0f64; n];
+        for w in order.iter().rev() {
+            let wu = w.as_();
+            let coeff = (1.0 + delta[wu]) / sigma[wu];
+            for v in g.in_neighbors(*w).iter().filter(|v| is_pred(**v, *w)) {
+                delta[v.as_()] += sigma[v.as_()] * coeff;
+            }
+            if *w != s {
+                bc[wu] += delta[wu];
+            }
+        }
+    })
+}
+
+/// Brandes' betweenness centrality with positive edge weights, using
+/// Dijkstra's algorithm from each source.
+pub fn weighted_betweenness_centrality<V, G, W>(g: &G, weights: fn(V, V) -> W, config: &BetweennessConfig) -> Vec<f64>
+where
+    G: Graph<V> + Sync,
+    V: PrimInt + AsPrimitive<usize> + std::hash::Hash + Send + Sync,
+    usize: AsPrimitive<V>,
+    W: num::Float,
+{
+    let n = g.nv().as_();
+    brandes(n, config, |s: V, bc: &mut [f64]| {
+        let paths = dijkstra_shortest_paths(g, s, weights);
+        let mut delta = vec![0.0f64; n];
+        for w in paths.order.iter().rev() {
+            let wu = w.as_();
+            let coeff = (1.0 + delta[wu]) / paths.sigma[wu];
+            for v in paths.preds[wu].iter() {
+                delta[v.as_()] += paths.sigma[v.as_()] * coeff;
+            }
+            if *w != s {
+                bc[wu] += delta[wu];
+            }
+        }
+    })
+}
+
+/// Closeness centrality with the Wasserman-Faust correction: for a vertex
+/// reaching `r - 1` others at total distance `d`, `(r - 1)^2 / ((n - 1) d)`.
+/// Distances are measured along out-edges.
+pub fn closeness_centrality<V, G>(g: &G) -> Vec<f64>
+where
+    G: Graph<V> + Sync,
+    V: PrimInt + AsPrimitive<usize> + Send + Sync,
+    usize: AsPrimitive<V>,
+{
+    let n = g.nv().as_();
+    all_sources_bfs_map(g, |_, levels| {
+        let reached = levels.iter().filter(|l| **l != V::max_value());
+        let (nreached, total) = reached.fold((0usize, 0usize), |(c, t), l| (c + 1, t + l.as_()));
+        if total == 0 {
+            0.0
+        } else {
+            let r = (nreached - 1) as f64;
+            r * r / ((n - 1) as f64 * total as f64)
+        }
+    })
+}
+
+/// Harmonic centrality: the sum of the inverse distances to every other
+/// vertex, with unreachable vertices contributing zero.
+pub fn harmonic_centrality<V, G>(g: &G) -> Vec<f64>
+where
+    G: Graph<V> + Sync,
+    V: PrimInt + AsPrimitive<usize> + Send + Sync,
+    usize: AsPrimitive<V>,
+{
+    all_sources_bfs_map(g, |_, levels| {
+        levels
+            .iter()
+            .filter(|l| **l != V::max_value() && **l != V::zero())
+            .map(|l| 1.0 / l.as_() as f64)
+            .sum()
+    })
+}
+
+/// Eccentricity of every vertex: the largest distance to any other vertex, or
+/// `V::max_value()` if some vertex cannot be reached.
+pub fn eccentricities<V, G>(g: &G) -> Vec<V>
+where
+    G: Graph<V> + Sync,
+    V: PrimInt + AsPrimitive<usize> + Send + Sync,
+    usize: AsPrimitive<V>,
+{
+    all_sources_bfs_map(g, |_, levels| levels.iter().cloned().max().unwrap_or_else(V::zero))
+}
+
+#[derive(Clone, Debug)]
+pub struct PowerIterationConfig {
+    /// Iteration stops once the L1 change of the scores is below this.
+    pub tolerance: f64,
+    pub max_iterations: usize,
+}
+
+impl Default for PowerIterationConfig {
+    fn default() -> Self {
+        PowerIterationConfig {
+            tolerance: 1e-6,
+            max_iterations: 100,
+        }
+    }
+}
+
+// y[v] = sum of x over the in-neighbors (or out-neighbors) of v, in parallel.
+fn neighbor_sums<V, G>(g: &G, x: &[f64], y: &mut [f64], incoming: bool)
+where
+    G: Graph<V> + Sync,
+    V: PrimInt + AsPrimitive<usize> + Send + Sync,
+    usize: AsPrimitive<V>,
+{
+    y.par_iter_mut().enumerate().for_each(|(v, yv)| {
+        let nbrs = if incoming { g.in_neighbors(v.as_()) } else { g.out_neighbors(v.as_()) };
+        *yv = nbrs.iter().map(|u| x[u.as_()]).sum();
+    });
+}
+
+// scales x to unit L2 norm (if not all zeros) in parallel.
+fn normalize_l2(x: &mut [f64]) {
+    let norm = x.par_iter().map(|a| a * a).sum::<f64>().sqrt();
+    if norm > 0.0 {
+        x.par_iter_mut().for_each(|a| *a /= norm);
+    }
+}
+
+// scales x to sum to one (if not all zeros) in parallel.
+fn normalize_l1(x: &mut [f64]) {
+    let total: f64 = x.par_iter().map(|a| a.abs()).sum();
+    if total > 0.0 {
+        x.par_iter_mut().for_each(|a| *a /= total);
+    }
+}
+
+fn l1_distance(x: &[f64], y: &[f64]) -> f64 {
+    x.par_iter().zip(y.par_iter()).map(|(a, b)| (a - b).abs()).sum()
+}
+
+/// Eigenvector centrality: the principal eigenvector of the transposed
+/// adjacency matrix, so that a vertex is central if pointed to by central
+/// vertices. Iterates with `A^T + I` so that periodic graphs converge too.
+/// Returns scores with unit L2 norm and the number of iterations performed.
+pub fn eigenvector_centrality<V, G>(g: &G, config: &PowerIterationConfig) -> (Vec<f64>, usize)
+where
+    G: Graph<V> + Sync,
+    V: PrimInt + AsPrimitive<usize> + Send + Sync,
+    usize: AsPrimitive<V>,
+{
+    let n = g.nv().as_();
+    let mut x = vec![1.0 / n as f64; n];
+    let mut next = vec![0.0f64; n];
+    let mut iterations = 0;
+    while iterations < config.max_iterations {
+        iterations += 1;
+        neighbor_sums(g, &x, &mut next, true);
+        next.par_iter_mut().zip(x.par_iter()).for_each(|(y, a)| *y += a);
+        normalize_l2(&mut next);
+        let err = l1_distance(&x, &next);
+        std::mem::swap(&mut x, &mut next);
+        if err < config.tolerance {
+            break;
+        }
+    }
+    (x, iterations)
+}
+
+/// Katz centrality, the fixed point of `x = alpha A^T x + beta`. `alpha` must
+/// be below the inverse of the largest eigenvalue of the adjacency matrix for
+/// the iteration to converge. Returns scores with unit L2 norm and the number
+/// of iterations performed.
+pub fn katz_centrality<V, G>(g: &G, alpha: f64, beta: f64, config: &PowerIterationConfig) -> (Vec<f64>, usize)
+where
+    G: Graph<V> + Sync,
+    V: PrimInt + AsPrimitive<usize> + Send + Sync,
+    usize: AsPrimitive<V>,
+{
+    let n = g.nv().as_();
+    let mut x = vec![0.0f64; n];
+    let mut next = vec![0.0f64; n];
+    let mut iterations = 0;
+    while iterations < config.max_iterations {
+        iterations += 1;
+        neighbor_sums(g, &x, &mut next, true);
+        next.par_iter_mut().for_each(|y| *y = alpha * *y + beta);
+        let err = l1_distance(&x, &next);
+        std::mem::swap(&mut x, &mut next);
+        if err < config.tolerance {
+            break;
+        }
+    }
+    normalize_l2(&mut x);
+    (x, iterations)
+}
+
+/// HITS hub and authority scores: authorities are pointed to by good hubs and
+/// hubs point to good authorities. Returns hubs and authorities, each summing
+/// to one, and the number of iterations performed.
+pub fn hits<V, G>(g: &G, config: &PowerIterationConfig) -> (Vec<f64>, Vec<f64>, usize)
+where
+    G: Graph<V> + Sync,
+    V: PrimInt + AsPrimitive<usize> + Send + Sync,
+    usize: AsPrimitive<V>,
+{
+    let n = g.nv().as_();
+    let mut hubs = vec![1.0 / n as f64; n];
+    let mut next = vec![0.0f64; n];
+    let mut authorities = vec![0.0f64; n];
+    let mut iterations = 0;
+    while iterations < config.max_iterations {
+        iterations += 1;
+        neighbor_sums(g, &hubs, &mut authorities, true);
+        normalize_l1(&mut authorities);
+        neighbor_sums(g, &authorities, &mut next, false);
+        normalize_l1(&mut next);
+        let err = l1_distance(&hubs, &next);
+        std::mem::swap(&mut hubs, &mut next);
+        if err < config.tolerance {
+            break;
+        }
+    }
+    (hubs, authorities, iterations)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::random_digraph;
+    use crate::{StaticDiGraph, StaticGraph, Vertex};
+
+    fn assert_close(a: f64, b: f64) {
+        assert!((a - b).abs() < 1e-6, "{} != {}", a, b);
+    }
+
+    fn star(k: Vertex) -> StaticGraph {
+        StaticGraph::from_edges(k + 1, (1..=k).map(|v| (0, v)).collect())
+    }
+
+    fn path(n: Vertex) -> StaticGraph {
+        StaticGraph::from_edges(n, (1..n).map(|v| (v - 1, v)).collect())
+    }
+
+    fn unit(_: Vertex, _: Vertex) -> f64 {
+        1.0
+    }
+
+    #[test]
+    fn pagerank_star() {
+        let config = PageRankConfig { tolerance: 1e-12, max_iterations: 1000, ..Default::default() };
+        let (scores, _) = pagerank(&star(4), &config);
+        // the center gets (1 + 4d) / (n (1 + d)), the leaves share the rest.
+        let d = config.damping;
+        let center = (1.0 + 4.0 * d) / (5.0 * (1.0 + d));
+        assert_close(scores[0], center);
+        scores[1..].iter().for_each(|s| assert_close(*s, (1.0 - center) / 4.0));
+    }
+
+    #[test]
+    fn pagerank_sums_to_one() {
+        let g = random_digraph(50, 150, 3);
+        let mut personalization = vec![0.0; 50];
+        personalization[7] = 2.0;
+        personalization[9] = 1.0;
+        for dangling in [Dangling::Uniform, Dangling::Personalization, Dangling::SelfLoop].iter() {
+            for p in [None, Some(personalization.clone())].iter() {
+                let config = PageRankConfig { dangling: *dangling, personalization: p.clone(), ..Default::default() };
+                let (scores, iterations) = pagerank(&g, &config);
+                assert!(iterations < config.max_iterations);
+                assert_close(scores.iter().sum(), 1.0);
+                assert!(scores.iter().all(|s| *s >= 0.0));
+            }
+        }
+        // a directed cycle is uniform.
+        let cycle = StaticDiGraph::from_edges(6, (0..6).map(|v| (v, (v + 1) % 6)).collect());
+        pagerank(&cycle, &Default::default()).0.iter().for_each(|s| assert_close(*s, 1.0 / 6.0));
+        assert_eq!(pagerank(&StaticDiGraph::from_edges(0, vec![]), &Default::default()), (vec![], 0));
+    }
+
+    #[test]
+    fn betweenness_path_and_star() {
+        // vertex i of a path is on the paths between i (n - 1 - i) ordered pairs.
+        let bc = betweenness_centrality(&path(6), &Default::default());
+        (0..6).for_each(|i| assert_close(bc[i], (2 * i * (5 - i)) as f64));
+        let wbc = weighted_betweenness_centrality(&path(6), unit, &Default::default());
+        (0..6).for_each(|i| assert_close(wbc[i], bc[i]));
+
+        let normalized = BetweennessConfig { normalized: true, ..Default::default() };
+        let bc = betweenness_centrality(&star(5), &normalized);
+        assert_close(bc[0], 1.0);
+        bc[1..].iter().for_each(|b| assert_close(*b, 0.0));
+
+        // two shortest paths between 0 and 3 through the square 0-1-3-2-0.
+        let square = StaticGraph::from_edges(4, vec![(0, 1), (1, 3), (3, 2), (2, 0)]);
+        betweenness_centrality(&square, &Default::default()).iter().for_each(|b| assert_close(*b, 1.0));
+    }
+
+    #[test]
+    fn sampled_betweenness() {
+        let g = random_digraph(40, 120, 5);
+        let exact = betweenness_centrality(&g, &Default::default());
+        let all = BetweennessConfig { samples: Some(40), ..Default::default() };
+        assert_eq!(betweenness_centrality(&g, &all), exact);
+
+        let sampled = BetweennessConfig { samples: Some(10), seed: 3, ..Default::default() };
+        let a = betweenness_centrality(&g, &sampled);
+        assert_eq!(a, betweenness_centrality(&g, &sampled));
+        assert!(a.iter().all(|b| *b >= 0.0));
+        // leaves are never between two other vertices, whatever the sources.
+        let bc = betweenness_centrality(&star(5), &BetweennessConfig { samples: Some(3), seed: 1, ..Default::default() });
+        assert!(bc[1..].iter().all(|b| *b == 0.0));
+    }
+
+    #[test]
+    fn distance_centralities_on_a_path() {
+        let g = path(5);
+        let closeness = closeness_centrality(&g);
+        let harmonic = harmonic_centrality(&g);
+        for i in 0..5i32 {
+            let total: i32 = (0..5).map(|j| (i - j).abs()).sum();
+            assert_close(closeness[i as usize], 4.0 / total as f64);
+            let inverse: f64 = (0..5).filter(|j| *j != i).map(|j| 1.0 / (i - j).abs() as f64).sum();
+            assert_close(harmonic[i as usize], inverse);
+        }
+        assert_eq!(eccentricities(&g), vec![4, 3, 2, 3, 4]);
+    }
+
+    #[test]
+    fn distance_centralities_when_disconnected() {
+        // a path 0 - 1 - 2 and an edge 3 - 4, plus an isolated vertex.
+        let g = StaticGraph::from_edges(6, vec![(0, 1), (1, 2), (3, 4)]);
+        let closeness = closeness_centrality(&g);
+        assert_close(closeness[1], 4.0 / (5.0 * 2.0));
+        assert_close(closeness[0], 4.0 / (5.0 * 3.0));
+        assert_close(closeness[3], 1.0 / 5.0);
+        assert_close(closeness[5], 0.0);
+        let harmonic = harmonic_centrality(&g);
+        assert_close(harmonic[0], 1.5);
+        assert_close(harmonic[5], 0.0);
+        assert!(eccentricities(&g).iter().all(|e| *e == Vertex::MAX));
+
+        // distances follow out-edges.
+        let d = StaticDiGraph::from_edges(3, vec![(0, 1), (1, 2)]);
+        assert_close(closeness_centrality(&d)[0], 4.0 / (2.0 * 3.0));
+        assert_close(closeness_centrality(&d)[2], 0.0);
+    }
+
+    #[test]
+    fn spectral_centralities_on_a_star() {
+        let config = PowerIterationConfig { tolerance: 1e-12, max_iterations: 1000 };
+        // the center is sqrt(k) times as central as each of the k leaves.
+        let (x, iterations) = eigenvector_centrality(&star(4), &config);
+        assert!(iterations < config.max_iterations);
+        assert_close(x[0], 0.5f64.sqrt());
+        x[1..].iter().for_each(|a| assert_close(*a, 1.0 / 8.0f64.sqrt()));
+
+        // leaves point to the center: x = beta for leaves, (1 + k alpha) beta
+        // for the center, before normalization.
+        let inward = StaticDiGraph::from_edges(5, (1..5).map(|v| (v, 0)).collect());
+        let (x, _) = katz_centrality(&inward, 0.1, 1.0, &config);
+        let norm = (1.4f64 * 1.4 + 4.0).sqrt();
+        assert_close(x[0], 1.4 / norm);
+        x[1..].iter().for_each(|a| assert_close(*a, 1.0 / norm));
+
+        let (hubs, authorities, _) = hits(&inward, &config);
+        assert_close(authorities[0], 1.0);
+        assert_close(hubs[0], 0.0);
+        authorities[1..].iter().for_each(|a| assert_close(*a, 0.0));
+        hubs[1..].iter().for_each(|h| assert_close(*h, 0.25));
+    }
+}
@@ -0,0 +1,307 @@
+use rayon::prelude::*;
+
+use num::cast::AsPrimitive;
+use num::traits::PrimInt;
+use crate::cores::degeneracy_ordering;
+use crate::parallel::{n_partitions, optimal_contiguous_partition};
+use crate::traits::Graph;
+use crate::triangles::{degree_ordered_dag, Dodg};
+
+// elements common to two sorted slices.
+fn intersection<V: PrimInt>(a: &[V], b: &[V]) -> Vec<V> {
+    let mut out = Vec::new();
+    let (mut i, mut j) = (0, 0);
+    while i < a.len() && j < b.len() {
+        if a[i] < b[j] {
+            i += 1;
+        } else if a[i] > b[j] {
+            j += 1;
+        } else {
+            out.push(a[i]);
+            i += 1;
+            j += 1;
+        }
+    }
+    out
+}
+
+// Bron-Kerbosch with Tomita pivoting: reports every maximal clique that
+// contains `r`, extends it with vertices of `p` and excludes those of `x`.
+// `p` and `x` are sorted.
+fn bron_kerbosch<V, F>(g: &impl Graph<V>, r: &mut Vec<V>, p: Vec<V>, mut x: Vec<V>, clique: &mut Vec<V>, f: &mut F)
+where
+    V: PrimInt + AsPrimitive<usize>,
+    F: FnMut(&[V]),
+{
+    if p.is_empty() {
+        if x.is_empty() {
+            clique.clear();
+            clique.extend_from_slice(r);
+            clique.sort_unstable();
+            f(clique);
+        }
+        return;
+    }
+    // the pivot has the most neighbors in p, which need not be tried.
+    let pivot = p.iter().chain(x.iter())
+        .max_by_key(|u| intersection(&p, g.out_neighbors(**u)).len())
+        .cloned()
+        .expect("Invalid candidates");
+    let pivot_nbrs = g.out_neighbors(pivot);
+    let mut remaining = p.clone();
+    for v in p.iter().filter(|v| **v == pivot || pivot_nbrs.binary_search(v).is_err()) {
+        let nbrs = g.out_neighbors(*v);
+        remaining.retain(|u| u != v);
+        r.push(*v);
+        bron_kerbosch(g, r, intersection(&remaining, nbrs), intersection(&x, nbrs), clique, f);
+        r.pop();
+        let pos = x.binary_search(v).unwrap_or_else(|e| e);
+        x.insert(pos, *v);
+    }
+}
+
+/// Calls `f` once for every maximal clique of an undirected graph, with its
+/// vertices in ascending order. Uses Bron-Kerbosch with pivoting, started from
+/// every vertex in degeneracy order (Eppstein, Löffler and Strash, 2010), so
+/// that each search only considers the later neighbors of its first vertex.
+/// Self-loops are ignored; isolated vertices are cliques of size one.
+pub fn for_each_maximal_clique<V, F>(g: &impl Graph<V>, mut f: F)
+where
+    V: PrimInt + AsPrimitive<usize>,
+    usize: AsPrimitive<V>,
+    F: FnMut(&[V]),
+{
+    let n = g.nv().as_();
+    let order = degeneracy_ordering(g);
+    let mut positions = vec![0usize; n];
+    for (i, v) in order.iter().enumerate() {
+        positions[v.as_()] = i;
+    }
+    let mut r: Vec<V> = Vec::new();
+    let mut clique: Vec<V> = Vec::new();
+    for v in order {
+        let pv = positions[v.as_()];
+        let (mut p, mut x): (Vec<V>, Vec<V>) = (Vec::new(), Vec::new());
+        for u in g.out_neighbors(v).iter().filter(|u| **u != v) {
+            if positions[u.as_()] > pv {
+                p.push(*u);
+            } else {
+                x.push(*u);
+            }
+        }
+        r.push(v);
+        bron_kerbosch(g, &mut r, p, x, &mut clique, &mut f);
+        r.pop();
+    }
+}
+
+// sorts the candidates by greedy color class; colors[i] is the number of
+// colors used by candidates[..=i], an upper bound on the size of any clique
+// among them.
+fn color_sort<V>(g: &impl Graph<V>, candidates: &[V]) -> (Vec<V>, Vec<usize>)
+where
+    V: PrimInt + AsPrimitive<usize>,
+{
+    let mut classes: Vec<Vec<V>> = Vec::new();
+    for v in candidates {
+        match classes.iter_mut().find(|c| c.iter().all(|u| !g.has_edge(*u, *v))) {
+            Some(c) => c.push(*v),
+            None => classes.push(vec![*v]),
+        }
+    }
+    let mut order = Vec::with_capacity(candidates.len());
+    let mut colors = Vec::with_capacity(candidates.len());
+    for (k, c) in classes.into_iter().enumerate() {
+        colors.resize(colors.len() + c.len(), k + 1);
+        order.extend(c);
+    }
+    (order, colors)
+}
+
+// branch and bound over the candidates that extend `r`, pruning branches that
+// cannot beat `best` according to the coloring bound (Tomita and Seki, 2003).
+fn expand<V>(g: &impl Graph<V>, r: &mut Vec<V>, candidates: Vec<V>, best: &mut Vec<V>)
+where
+    V: PrimInt + AsPrimitive<usize>,
+{
+    if candidates.is_empty() {
+        if r.len() > best.len() {
+            best.clear();
+            best.extend_from_slice(r);
+        }
+        return;
+    }
+    let (order, colors) = color_sort(g, &candidates);
+    for i in (0..order.len()).rev() {
+        if r.len() + colors[i] <= best.len() {
+            return;
+        }
+        let v = order[i];
+        let next: Vec<V> = order[..i].iter().filter(|u| g.has_edge(v, **u)).cloned().collect();
+        r.push(v);
+        expand(g, r, next, best);
+        r.pop();
+    }
+}
+
+/// A maximum clique of an undirected graph, in ascending order. Searches from
+/// every vertex with its later neighbors in degeneracy order, bounding each
+/// search by a greedy coloring of the candidates.
+pub fn maximum_clique<V>(g: &impl Graph<V>) -> Vec<V>
+where
+    V: PrimInt + AsPrimitive<usize>,
+    usize: AsPrimitive<V>,
+{
+    let n = g.nv().as_();
+    let order = degeneracy_ordering(g);
+    let mut positions = vec![0usize; n];
+    for (i, v) in order.iter().enumerate() {
+        positions[v.as_()] = i;
+    }
+    let mut best: Vec<V> = Vec::new();
+    let mut r: Vec<V> = Vec::new();
+    for v in order.into_iter().rev() {
+        let pv = positions[v.as_()];
+        let later: Vec<V> = g.out_neighbors(v).iter().filter(|u| positions[u.as_()] > pv).cloned().collect();
+        if later.len() < best.len() {
+            continue;
+        }
+        r.push(v);
+        expand(g, &mut r, later, &mut best);
+        r.pop();
+    }
+    best.sort_unstable();
+    best
+}
+
+// number of cliques of `l` more vertices among `candidates`, all of which are
+// out-neighbors in the DAG of the vertices chosen so far.
+fn count_cliques<V>(dodg: &Dodg<V>, candidates: &[V], l: usize) -> u128
+where
+    V: PrimInt + AsPrimitive<usize>,
+{
+    if l == 1 {
+        return candidates.len() as u128;
+    }
+    candidates.iter()
+        .map(|v| {
+            let next = intersection(candidates, dodg.row(v.as_()));
+            if next.len() + 1 < l { 0 } else { count_cliques(dodg, &next, l - 1) }
+        })
+        .sum()
+}
+
+/// Number of cliques of `k` vertices of an undirected graph. Generalizes
+/// `threaded_triangles`: edges are oriented from lower to higher degree, so
+/// that every clique is found exactly once from its lowest vertex, by
+/// repeatedly intersecting out-neighborhoods in the resulting DAG.
+pub fn k_cliques<V, G>(g: &G, k: usize) -> u128
+where
+    G: Graph<V> + Sync,
+    V: PrimInt + AsPrimitive<usize> + Send + Sync,
+    usize: AsPrimitive<V>,
+{
+    assert!(k > 0, "cliques have at least one vertex");
+    if k == 1 {
+        return g.nv().as_() as u128;
+    }
+    let (_, dodg) = degree_ordered_dag(g);
+    let weights: Vec<usize> = (0..dodg.dim()).map(|r| dodg.row_len(r).pow(2)).collect();
+    let partitions = optimal_contiguous_partition(&weights, n_partitions());
+    partitions.into_par_iter()
+        .map(|p| p.map(|u| count_cliques(&dodg, dodg.row(u), k - 1)).sum::<u128>())
+        .sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::random_graph;
+    use crate::triangles::triangles;
+    use crate::{StaticGraph, Vertex};
+
+    fn is_clique(g: &StaticGraph, vs: &[Vertex]) -> bool {
+        vs.iter().enumerate().all(|(i, u)| vs[(i + 1)..].iter().all(|v| g.has_edge(*u, *v)))
+    }
+
+    // every clique of a small graph, as sorted vertex lists.
+    fn all_cliques(g: &StaticGraph) -> Vec<Vec<Vertex>> {
+        let n = g.nv();
+        (1..(1u32 << n))
+            .map(|mask| (0..n).filter(|v| mask >> v & 1 == 1).collect::<Vec<_>>())
+            .filter(|vs| is_clique(g, vs))
+            .collect()
+    }
+
+    // Moon-Moser graph: the complement of k disjoint triangles, with 3^k
+    // maximal cliques of size k.
+    fn moon_moser(k: Vertex) -> StaticGraph {
+        let edges = (0..3 * k)
+            .flat_map(|u| ((u + 1)..3 * k).filter(move |v| u / 3 != v / 3).map(move |v| (u, v)))
+            .collect();
+        StaticGraph::from_edges(3 * k, edges)
+    }
+
+    #[test]
+    fn maximal_cliques_brute_force() {
+        // self-loops are ignored.
+        let mut graphs = vec![StaticGraph::from_edges(5, vec![(0, 0), (0, 1), (1, 2), (2, 0), (2, 3), (3, 3)])];
+        graphs.extend((0..20).map(|seed| random_graph(12, 10 + 2 * seed as usize, seed)));
+        for g in graphs {
+            let cliques = all_cliques(&g);
+            let mut maximal: Vec<Vec<Vertex>> = cliques.iter()
+                .filter(|c| g.vertices().all(|v| c.contains(&v) || !c.iter().all(|u| g.has_edge(*u, v))))
+                .cloned()
+                .collect();
+            maximal.sort();
+            let mut found: Vec<Vec<Vertex>> = Vec::new();
+            for_each_maximal_clique(&g, |c| found.push(c.to_vec()));
+            found.sort();
+            assert_eq!(found, maximal);
+
+            let largest = cliques.iter().map(|c| c.len()).max().unwrap();
+            let best = maximum_clique(&g);
+            assert_eq!(best.len(), largest);
+            assert!(is_clique(&g, &best));
+            for k in 1..6 {
+                assert_eq!(k_cliques(&g, k), cliques.iter().filter(|c| c.len() == k).count() as u128);
+            }
+        }
+    }
+
+    #[test]
+    fn known_clique_numbers() {
+        let g = moon_moser(5);
+        let mut count = 0;
+        for_each_maximal_clique(&g, |c| {
+            assert_eq!(c.len(), 5);
+            count += 1;
+        });
+        assert_eq!(count, 243);
+        assert_eq!(maximum_clique(&g).len(), 5);
+        assert_eq!(k_cliques(&g, 5), 243);
+
+        // a 9-clique planted in a sparse random graph.
+        let base = random_graph(300, 900, 4);
+        let mut edges: Vec<(Vertex, Vertex)> = base.vertices()
+            .flat_map(|u| base.out_neighbors(u).iter().filter(move |v| **v > u).map(move |v| (u, *v)))
+            .collect();
+        let planted: Vec<Vertex> = (0..9).map(|i| 7 + 31 * i).collect();
+        for (i, u) in planted.iter().enumerate() {
+            edges.extend(planted[(i + 1)..].iter().map(|v| (*u, *v)));
+        }
+        let g = StaticGraph::from_edges(300, edges);
+        assert_eq!(maximum_clique(&g), planted);
+        assert_eq!(k_cliques(&g, 9), 1);
+    }
+
+    #[test]
+    fn k_cliques_count_triangles() {
+        for seed in 0..10 {
+            let g = random_graph(400, 4000, seed);
+            assert_eq!(k_cliques(&g, 3), triangles(&g).0);
+            assert_eq!(k_cliques(&g, 2), g.ne() as u128 / 2);
+            assert_eq!(k_cliques(&g, 1), 400);
+        }
+    }
+}
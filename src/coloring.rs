@@ -0,0 +1,259 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::SeedableRng;
+use rayon::prelude::*;
+
+use num::cast::AsPrimitive;
+use num::traits::PrimInt;
+use crate::cores::degeneracy_ordering;
+use crate::parallel::mix;
+use crate::traits::Graph;
+
+/// Order in which vertices are colored.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ColoringOrder {
+    /// Ascending vertex ids.
+    Natural,
+    /// A random permutation.
+    Random,
+    /// Nonincreasing degree, ties in random order (Welsh and Powell, 1967).
+    LargestFirst,
+    /// Reverse degeneracy order, which uses at most degeneracy + 1 colors.
+    SmallestLast,
+}
+
+fn coloring_order<V>(g: &impl Graph<V>, order: ColoringOrder, seed: u64) -> Vec<V>
+where
+    V: PrimInt + AsPrimitive<usize>,
+    usize: AsPrimitive<V>,
+{
+    let mut rng = StdRng::seed_from_u64(seed);
+    match order {
+        ColoringOrder::Natural => g.vertices().collect(),
+        ColoringOrder::Random => {
+            let mut vs: Vec<V> = g.vertices().collect();
+            vs.shuffle(&mut rng);
+            vs
+        }
+        ColoringOrder::LargestFirst => {
+            let mut vs: Vec<V> = g.vertices().collect();
+            vs.shuffle(&mut rng);
+            vs.sort_by_key(|v| std::cmp::Reverse(g.out_degree(*v)));
+            vs
+        }
+        ColoringOrder::SmallestLast => {
+            let mut vs = degeneracy_ordering(g);
+            vs.reverse();
+            vs
+        }
+    }
+}
+
+// the smallest color not in `used`, which is consumed.
+fn first_free(used: &mut Vec<usize>) -> usize {
+    used.sort_unstable();
+    used.dedup();
+    let c = used.iter().enumerate().find(|(i, c)| *i != **c).map_or(used.len(), |(i, _)| i);
+    used.clear();
+    c
+}
+
+fn ncolors(colors: &[usize]) -> usize {
+    colors.iter().max().map_or(0, |c| c + 1)
+}
+
+/// Greedy vertex coloring: vertices are visited in the given order, each
+/// taking the smallest color not used by its neighbors. Self-loops are
+/// ignored. Returns the color of every vertex and the number of colors.
+pub fn greedy_coloring<V>(g: &impl Graph<V>, order: ColoringOrder, seed: u64) -> (Vec<V>, usize)
+where
+    V: PrimInt + AsPrimitive<usize>,
+    usize: AsPrimitive<V>,
+{
+    let mut colors = vec![usize::MAX; g.nv().as_()];
+    let mut used: Vec<usize> = Vec::new();
+    for v in coloring_order(g, order, seed) {
+        used.extend(g.out_neighbors(v).iter().map(|u| colors[u.as_()]).filter(|c| *c != usize::MAX));
+        colors[v.as_()] = first_free(&mut used);
+    }
+    let k = ncolors(&colors);
+    (colors.into_iter().map(|c| c.as_()).collect(), k)
+}
+
+/// Jones-Plassmann parallel vertex coloring. Every vertex waits for its
+/// neighbors earlier in the given order, and all vertices whose earlier
+/// neighbors are colored are colored at once, so the result is the same as
+/// `greedy_coloring` with the same order and seed.
+pub fn jones_plassmann_coloring<V, G>(g: &G, order: ColoringOrder, seed: u64) -> (Vec<V>, usize)
+where
+    G: Graph<V> + Sync,
+    V: PrimInt + AsPrimitive<usize> + Send + Sync,
+    usize: AsPrimitive<V>,
+{
+    let n = g.nv().as_();
+    let mut ranks = vec![0usize; n];
+    for (i, v) in coloring_order(g, order, seed).into_iter().enumerate() {
+        ranks[v.as_()] = i;
+    }
+    // number of uncolored neighbors earlier in the order.
+    let waiting: Vec<AtomicUsize> = (0..n).into_par_iter().map(|vu| {
+        let rv = ranks[vu];
+        AtomicUsize::new(g.out_neighbors(vu.as_()).iter().filter(|u| ranks[u.as_()] < rv).count())
+    }).collect();
+    let colors: Vec<AtomicUsize> = (0..n).map(|_| AtomicUsize::new(usize::MAX)).collect();
+
+    let mut frontier: Vec<V> = (0..n).into_par_iter()
+        .filter(|vu| waiting[*vu].load(Ordering::Relaxed) == 0)
+        .map(|vu| vu.as_())
+        .collect();
+    while !frontier.is_empty() {
+        // frontier vertices are never adjacent, since one of two neighbors
+        // waits for the other.
+        frontier.par_iter().for_each(|v| {
+            let mut used: Vec<usize> = g.out_neighbors(*v).iter()
+                .map(|u| colors[u.as_()].load(Ordering::Relaxed))
+                .filter(|c| *c != usize::MAX)
+                .collect();
+            colors[v.as_()].store(first_free(&mut used), Ordering::Relaxed);
+        });
+        frontier = frontier.par_iter().flat_map(|v| {
+            let rv = ranks[v.as_()];
+            g.out_neighbors(*v).iter()
+                .filter(|u| ranks[u.as_()] > rv && waiting[u.as_()].fetch_sub(1, Ordering::Relaxed) == 1)
+                .cloned()
+                .collect::<Vec<_>>()
+        }).collect();
+    }
+    let colors: Vec<usize> = colors.into_iter().map(|c| c.into_inner()).collect();
+    let k = ncolors(&colors);
+    (colors.into_iter().map(|c| c.as_()).collect(), k)
+}
+
+/// Luby's parallel maximal independent set. In each round every undecided
+/// vertex draws a pseudo-random priority from the seed, joins the set if it
+/// beats all its undecided neighbors, and the neighbors of joining vertices
+/// are excluded. Self-loops are ignored. Returns the set in ascending order.
+pub fn luby_maximal_independent_set<V, G>(g: &G, seed: u64) -> Vec<V>
+where
+    G: Graph<V> + Sync,
+    V: PrimInt + AsPrimitive<usize> + Send + Sync,
+    usize: AsPrimitive<V>,
+{
+    const UNDECIDED: u8 = 0;
+    const IN: u8 = 1;
+    const OUT: u8 = 2;
+    let n = g.nv().as_();
+    let mut state = vec![UNDECIDED; n];
+    let mut undecided: Vec<V> = g.vertices().collect();
+    let mut round = 0u64;
+    while !undecided.is_empty() {
+        round += 1;
+        let salt = mix(seed ^ mix(round));
+        let priority = |v: V| (mix(salt ^ v.as_() as u64), v);
+        let state_ref = &state;
+        let joining: Vec<V> = undecided.par_iter()
+            .filter(|v| {
+                let pv = priority(**v);
+                g.out_neighbors(**v).iter()
+                    .all(|u| *u == **v || state_ref[u.as_()] != UNDECIDED || pv < priority(*u))
+            })
+            .cloned()
+            .collect();
+        for v in joining.iter() {
+            state[v.as_()] = IN;
+        }
+        let state_ref = &state;
+        let excluded: Vec<V> = undecided.par_iter()
+            .filter(|v| state_ref[v.as_()] == UNDECIDED && g.out_neighbors(**v).iter().any(|u| state_ref[u.as_()] == IN))
+            .cloned()
+            .collect();
+        for v in excluded.iter() {
+            state[v.as_()] = OUT;
+        }
+        let state_ref = &state;
+        undecided = undecided.into_par_iter().filter(|v| state_ref[v.as_()] == UNDECIDED).collect();
+    }
+    g.vertices().filter(|v| state[v.as_()] == IN).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cores::degeneracy;
+    use crate::testing::random_graph;
+    use crate::parallel::with_threads;
+    use crate::{StaticGraph, Vertex};
+
+    const ORDERS: [ColoringOrder; 4] =
+        [ColoringOrder::Natural, ColoringOrder::Random, ColoringOrder::LargestFirst, ColoringOrder::SmallestLast];
+
+    fn assert_proper(g: &StaticGraph, colors: &[Vertex], ncolors: usize) {
+        for u in g.vertices() {
+            assert!((colors[u as usize] as usize) < ncolors);
+            assert!(g.out_neighbors(u).iter().all(|v| *v == u || colors[*v as usize] != colors[u as usize]));
+        }
+    }
+
+    fn graphs() -> Vec<StaticGraph> {
+        let mut graphs: Vec<StaticGraph> = (0..5).map(|seed| random_graph(300, 1500, seed)).collect();
+        // a self-loop, an isolated vertex and an empty graph.
+        graphs.push(StaticGraph::from_edges(4, vec![(0, 0), (0, 1), (1, 2)]));
+        graphs.push(StaticGraph::from_edges(0, vec![]));
+        graphs
+    }
+
+    #[test]
+    fn colorings_are_proper() {
+        for g in graphs().iter() {
+            for order in ORDERS.iter() {
+                let (colors, ncolors) = greedy_coloring(g, *order, 3);
+                assert_proper(g, &colors, ncolors);
+                let maxdeg = g.vertices().map(|v| g.out_degree(v)).max().unwrap_or(0);
+                assert!(ncolors <= maxdeg as usize + 1);
+                if *order == ColoringOrder::SmallestLast && g.nv() > 0 {
+                    assert!(ncolors <= degeneracy(g) as usize + 1);
+                }
+            }
+        }
+        // an odd cycle needs three colors, an even one two.
+        let odd = StaticGraph::from_edges(7, (0..7).map(|v| (v, (v + 1) % 7)).collect());
+        assert_eq!(greedy_coloring(&odd, ColoringOrder::Natural, 0).1, 3);
+        let even = StaticGraph::from_edges(8, (0..8).map(|v| (v, (v + 1) % 8)).collect());
+        assert_eq!(greedy_coloring(&even, ColoringOrder::Natural, 0).1, 2);
+    }
+
+    #[test]
+    fn jones_plassmann_matches_greedy() {
+        for g in graphs().iter() {
+            for order in ORDERS.iter() {
+                for seed in 0..3 {
+                    let expected = greedy_coloring(g, *order, seed);
+                    for nthreads in 1..5 {
+                        let jp = with_threads(Some(nthreads), || jones_plassmann_coloring(g, *order, seed)).unwrap();
+                        assert_eq!(jp, expected);
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn luby_is_maximal_independent() {
+        for g in graphs().iter() {
+            for seed in 0..5 {
+                let set = luby_maximal_independent_set(g, seed);
+                let mut in_set = vec![false; g.nv() as usize];
+                set.iter().for_each(|v| in_set[*v as usize] = true);
+                for v in g.vertices() {
+                    let covered = g.out_neighbors(v).iter().any(|u| *u != v && in_set[*u as usize]);
+                    // independent, and every other vertex has a neighbor in it.
+                    assert!(in_set[v as usize] != covered);
+                }
+                for nthreads in 1..5 {
+                    assert_eq!(with_threads(Some(nthreads), || luby_maximal_independent_set(g, seed)).unwrap(), set);
+                }
+            }
+        }
+    }
+}
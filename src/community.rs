@@ -0,0 +1,833 @@
+use std::collections::{HashMap, VecDeque};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::{Rng, SeedableRng};
+use rayon::prelude::*;
+
+use num::cast::AsPrimitive;
+use num::traits::PrimInt;
+use crate::parallel::mix;
+use crate::traits::Graph;
+
+#[derive(Clone, Debug)]
+pub struct CommunityConfig {
+    /// Weight of the null model in the quality function; higher values give
+    /// more and smaller communities. 1.0 optimizes plain modularity.
+    pub resolution: f64,
+    /// Optimization stops once a level improves the quality by less than this.
+    pub tolerance: f64,
+    pub max_levels: usize,
+    /// Leiden only: how random the refinement merges are. Lower values pick
+    /// the best merge more often.
+    pub randomness: f64,
+    /// Seed for the order in which vertices are visited.
+    pub seed: u64,
+}
+
+impl Default for CommunityConfig {
+    fn default() -> Self {
+        CommunityConfig {
+            resolution: 1.0,
+            tolerance: 1e-7,
+            max_levels: 100,
+            randomness: 0.01,
+            seed: 0,
+        }
+    }
+}
+
+pub struct Communities<V> {
+    /// Community of every vertex, numbered from zero.
+    pub partition: Vec<V>,
+    /// Modularity of `partition`, at resolution 1.
+    pub modularity: f64,
+    /// Partition of the vertices found at each level, from the finest to the
+    /// coarsest. The last one is `partition`.
+    pub levels: Vec<Vec<V>>,
+}
+
+// undirected weighted graph whose communities are optimized. The vertices of
+// each level are the communities of the previous one.
+#[derive(Clone)]
+struct Network {
+    offsets: Vec<usize>,
+    targets: Vec<usize>,
+    weights: Vec<f64>,
+    // weight of the self-loop of every vertex, counted once.
+    loops: Vec<f64>,
+    // weighted degrees, in which self-loops count twice.
+    degrees: Vec<f64>,
+    // sum of the degrees, twice the total edge weight.
+    total: f64,
+}
+
+impl Network {
+    fn from_graph<V, F>(g: &impl Graph<V>, weight: F) -> Self
+    where
+        V: PrimInt + AsPrimitive<usize>,
+        F: Fn(V, V) -> f64,
+    {
+        let n = g.nv().as_();
+        let mut offsets = Vec::with_capacity(n + 1);
+        let mut targets = Vec::new();
+        let mut weights = Vec::new();
+        let mut loops = vec![0.0f64; n];
+        offsets.push(0);
+        for u in g.vertices() {
+            for v in g.out_neighbors(u) {
+                let w = weight(u, *v);
+                if *v == u {
+                    loops[u.as_()] += w;
+                } else {
+                    targets.push(v.as_());
+                    weights.push(w);
+                }
+            }
+            offsets.push(targets.len());
+        }
+        Network::new(offsets, targets, weights, loops)
+    }
+
+    fn new(offsets: Vec<usize>, targets: Vec<usize>, weights: Vec<f64>, loops: Vec<f64>) -> Self {
+        let degrees: Vec<f64> = (0..loops.len())
+            .map(|i| weights[offsets[i]..offsets[i + 1]].iter().sum::<f64>() + 2.0 * loops[i])
+            .collect();
+        let total = degrees.iter().sum();
+        Network { offsets, targets, weights, loops, degrees, total }
+    }
+
+    fn nv(&self) -> usize {
+        self.loops.len()
+    }
+
+    fn neighbors(&self, i: usize) -> impl Iterator<Item = (usize, f64)> + '_ {
+        let r = self.offsets[i]..self.offsets[i + 1];
+        self.targets[r.clone()].iter().cloned().zip(self.weights[r].iter().cloned())
+    }
+
+    // merges every community of `comm`, numbered below `k`, into one vertex.
+    fn aggregate(&self, comm: &[usize], k: usize) -> Network {
+        let mut loops = vec![0.0f64; k];
+        let mut edges: Vec<(usize, usize, f64)> = Vec::new();
+        for i in 0..self.nv() {
+            let ci = comm[i];
+            loops[ci] += self.loops[i];
+            for (j, w) in self.neighbors(i) {
+                if comm[j] == ci {
+                    // seen from both endpoints.
+                    loops[ci] += w / 2.0;
+                } else {
+                    edges.push((ci, comm[j], w));
+                }
+            }
+        }
+        edges.sort_unstable_by_key(|e| (e.0, e.1));
+        let mut offsets = vec![0usize; k + 1];
+        let mut targets: Vec<usize> = Vec::new();
+        let mut weights: Vec<f64> = Vec::new();
+        let mut last: Option<(usize, usize)> = None;
+        for (c, d, w) in edges {
+            if last == Some((c, d)) {
+                *weights.last_mut().expect("Invalid weights") += w;
+            } else {
+                targets.push(d);
+                weights.push(w);
+                offsets[c + 1] += 1;
+                last = Some((c, d));
+            }
+        }
+        for c in 0..k {
+            offsets[c + 1] += offsets[c];
+        }
+        Network::new(offsets, targets, weights, loops)
+    }
+
+    // generalized modularity of `comm`, numbered below the number of vertices.
+    fn quality(&self, comm: &[usize], resolution: f64) -> f64 {
+        if self.total == 0.0 {
+            return 0.0;
+        }
+        let n = self.nv();
+        let mut internal = vec![0.0f64; n];
+        let mut tot = vec![0.0f64; n];
+        for i in 0..n {
+            let ci = comm[i];
+            tot[ci] += self.degrees[i];
+            internal[ci] += 2.0 * self.loops[i];
+            internal[ci] += self.neighbors(i).filter(|(j, _)| comm[*j] == ci).map(|(_, w)| w).sum::<f64>();
+        }
+        (0..n).map(|c| internal[c] / self.total - resolution * (tot[c] / self.total).powi(2)).sum()
+    }
+}
+
+// renumbers labels from zero in order of first appearance. Returns the number
+// of distinct labels.
+fn renumber(labels: &mut [usize]) -> usize {
+    let mut ids: HashMap<usize, usize> = HashMap::new();
+    for l in labels.iter_mut() {
+        let next = ids.len();
+        *l = *ids.entry(*l).or_insert(next);
+    }
+    ids.len()
+}
+
+// accumulates the weight from one vertex to each neighboring community.
+struct NeighborWeights {
+    weight: Vec<f64>,
+    seen: Vec<usize>,
+}
+
+impl NeighborWeights {
+    fn new(n: usize) -> Self {
+        NeighborWeights { weight: vec![0.0; n], seen: Vec::new() }
+    }
+
+    fn collect(&mut self, net: &Network, i: usize, comm: &[usize]) {
+        for c in self.seen.drain(..) {
+            self.weight[c] = 0.0;
+        }
+        for (j, w) in net.neighbors(i) {
+            let c = comm[j];
+            if self.weight[c] == 0.0 {
+                self.seen.push(c);
+            }
+            self.weight[c] += w;
+        }
+    }
+}
+
+// a vertex only leaves its community for a gain larger than this, relative to
+// its degree, so that rounding errors cannot make vertices move back and forth
+// between communities of equal quality forever.
+const MIN_GAIN: f64 = 1e-10;
+
+// Louvain local moving: repeatedly moves every vertex to the neighboring
+// community with the best quality gain, until no vertex moves. Returns true
+// if any vertex moved.
+fn move_nodes(net: &Network, comm: &mut [usize], resolution: f64, rng: &mut StdRng) -> bool {
+    let n = net.nv();
+    let mut tot = vec![0.0f64; n];
+    for i in 0..n {
+        tot[comm[i]] += net.degrees[i];
+    }
+    let mut order: Vec<usize> = (0..n).collect();
+    order.shuffle(rng);
+    let mut nw = NeighborWeights::new(n);
+    let mut any = false;
+    loop {
+        let mut moved = false;
+        for &i in order.iter() {
+            let (ci, ki) = (comm[i], net.degrees[i]);
+            nw.collect(net, i, comm);
+            tot[ci] -= ki;
+            let gain = |c: usize| nw.weight[c] - resolution * tot[c] * ki / net.total;
+            let mut best = ci;
+            let mut best_gain = gain(ci) + MIN_GAIN * ki;
+            for &c in nw.seen.iter() {
+                let g = gain(c);
+                if g > best_gain {
+                    best = c;
+                    best_gain = g;
+                }
+            }
+            tot[best] += ki;
+            if best != ci {
+                comm[i] = best;
+                moved = true;
+            }
+        }
+        if !moved {
+            return any;
+        }
+        any = true;
+    }
+}
+
+// Leiden fast local moving: like `move_nodes`, but only revisits the
+// neighbors of vertices that moved, and may move a vertex to an empty
+// community.
+fn fast_move_nodes(net: &Network, comm: &mut [usize], resolution: f64, rng: &mut StdRng) -> bool {
+    let n = net.nv();
+    let mut tot = vec![0.0f64; n];
+    let mut size = vec![0usize; n];
+    for i in 0..n {
+        tot[comm[i]] += net.degrees[i];
+        size[comm[i]] += 1;
+    }
+    let mut empty: Vec<usize> = (0..n).filter(|c| size[*c] == 0).collect();
+    let mut order: Vec<usize> = (0..n).collect();
+    order.shuffle(rng);
+    let mut queue: VecDeque<usize> = order.into_iter().collect();
+    let mut queued = vec![true; n];
+    let mut nw = NeighborWeights::new(n);
+    let mut any = false;
+    while let Some(i) = queue.pop_front() {
+        queued[i] = false;
+        let (ci, ki) = (comm[i], net.degrees[i]);
+        nw.collect(net, i, comm);
+        tot[ci] -= ki;
+        size[ci] -= 1;
+        if size[ci] == 0 {
+            empty.push(ci);
+        }
+        let gain = |c: usize| nw.weight[c] - resolution * tot[c] * ki / net.total;
+        let mut best = ci;
+        let mut best_gain = gain(ci) + MIN_GAIN * ki;
+        for &c in nw.seen.iter() {
+            let g = gain(c);
+            if g > best_gain {
+                best = c;
+                best_gain = g;
+            }
+        }
+        if best_gain < 0.0 {
+            best = *empty.last().expect("Invalid empty communities");
+        }
+        if size[best] == 0 {
+            // either the last empty community or ci, which was just added.
+            empty.pop();
+        }
+        tot[best] += ki;
+        size[best] += 1;
+        if best != ci {
+            comm[i] = best;
+            any = true;
+            for (j, _) in net.neighbors(i) {
+                if !queued[j] && comm[j] != best {
+                    queued[j] = true;
+                    queue.push_back(j);
+                }
+            }
+        }
+    }
+    any
+}
+
+// Leiden refinement: splits every community of `comm` into well-connected
+// subcommunities by merging singletons, each picking a subcommunity at random
+// with probability increasing with the quality gain.
+fn refine(net: &Network, comm: &[usize], resolution: f64, randomness: f64, rng: &mut StdRng) -> Vec<usize> {
+    let n = net.nv();
+    let mut comm_tot = vec![0.0f64; n];
+    for i in 0..n {
+        comm_tot[comm[i]] += net.degrees[i];
+    }
+    let mut refined: Vec<usize> = (0..n).collect();
+    let mut tot = net.degrees.clone();
+    let mut size = vec![1usize; n];
+    // weight from each subcommunity to the rest of its community.
+    let mut external: Vec<f64> = (0..n)
+        .map(|i| net.neighbors(i).filter(|(j, _)| comm[*j] == comm[i]).map(|(_, w)| w).sum())
+        .collect();
+    let well_connected = |ext: f64, t: f64, s: f64| ext >= resolution * t * (s - t) / net.total;
+
+    let mut order: Vec<usize> = (0..n).collect();
+    order.shuffle(rng);
+    let mut nw = NeighborWeights::new(n);
+    let mut candidates: Vec<(usize, f64)> = Vec::new();
+    for &i in order.iter() {
+        let (ri, ki, s) = (refined[i], net.degrees[i], comm_tot[comm[i]]);
+        if size[ri] > 1 || !well_connected(external[ri], ki, s) {
+            continue;
+        }
+        nw.collect(net, i, &refined);
+        candidates.clear();
+        candidates.push((ri, 0.0));
+        for &r in nw.seen.iter() {
+            if r == ri || comm[r] != comm[i] || !well_connected(external[r], tot[r], s) {
+                continue;
+            }
+            let gain = nw.weight[r] - resolution * tot[r] * ki / net.total;
+            if gain >= 0.0 {
+                candidates.push((r, 2.0 * gain / net.total));
+            }
+        }
+        let top = candidates.iter().map(|c| c.1).fold(0.0, f64::max);
+        let probs: Vec<f64> = candidates.iter().map(|c| ((c.1 - top) / randomness).exp()).collect();
+        let mut x = rng.gen::<f64>() * probs.iter().sum::<f64>();
+        let mut chosen = candidates.last().expect("Invalid candidates").0;
+        for (c, p) in candidates.iter().zip(probs.iter()) {
+            if x < *p {
+                chosen = c.0;
+                break;
+            }
+            x -= p;
+        }
+        if chosen != ri {
+            external[chosen] += external[ri] - 2.0 * nw.weight[chosen];
+            tot[chosen] += ki;
+            size[chosen] += 1;
+            tot[ri] = 0.0;
+            size[ri] = 0;
+            refined[i] = chosen;
+        }
+    }
+    refined
+}
+
+fn finish<V>(net: &Network, levels: Vec<Vec<V>>) -> Communities<V>
+where
+    V: PrimInt + AsPrimitive<usize>,
+    usize: AsPrimitive<V>,
+{
+    let partition = levels.last().cloned().unwrap_or_default();
+    let labels: Vec<usize> = partition.iter().map(|c| c.as_()).collect();
+    Communities { modularity: net.quality(&labels, 1.0), partition, levels }
+}
+
+fn louvain_network<V>(mut net: Network, config: &CommunityConfig) -> Communities<V>
+where
+    V: PrimInt + AsPrimitive<usize>,
+    usize: AsPrimitive<V>,
+{
+    let original = net.clone();
+    let mut rng = StdRng::seed_from_u64(config.seed);
+    let mut node_of: Vec<usize> = (0..net.nv()).collect();
+    let mut levels: Vec<Vec<V>> = Vec::new();
+    let mut prev = net.quality(&node_of, config.resolution);
+    while levels.len() < config.max_levels {
+        let mut comm: Vec<usize> = (0..net.nv()).collect();
+        let moved = move_nodes(&net, &mut comm, config.resolution, &mut rng);
+        if !moved && !levels.is_empty() {
+            break;
+        }
+        let k = renumber(&mut comm);
+        let q = net.quality(&comm, config.resolution);
+        node_of.iter_mut().for_each(|i| *i = comm[*i]);
+        levels.push(node_of.iter().map(|c| c.as_()).collect());
+        if !moved || q - prev < config.tolerance {
+            break;
+        }
+        prev = q;
+        net = net.aggregate(&comm, k);
+    }
+    finish(&original, levels)
+}
+
+fn leiden_network<V>(mut net: Network, config: &CommunityConfig) -> Communities<V>
+where
+    V: PrimInt + AsPrimitive<usize>,
+    usize: AsPrimitive<V>,
+{
+    assert!(config.randomness > 0.0, "randomness must be positive");
+    let original = net.clone();
+    let mut rng = StdRng::seed_from_u64(config.seed);
+    let mut node_of: Vec<usize> = (0..net.nv()).collect();
+    let mut comm: Vec<usize> = (0..net.nv()).collect();
+    let mut levels: Vec<Vec<V>> = Vec::new();
+    let mut prev = net.quality(&comm, config.resolution);
+    while levels.len() < config.max_levels {
+        fast_move_nodes(&net, &mut comm, config.resolution, &mut rng);
+        let k = renumber(&mut comm);
+        let q = net.quality(&comm, config.resolution);
+        let partition: Vec<V> = node_of.iter().map(|i| comm[*i].as_()).collect();
+        // an unchanged partition can still improve once its refined
+        // subcommunities have been merged further.
+        if levels.last() != Some(&partition) {
+            levels.push(partition);
+            if q - prev < config.tolerance {
+                break;
+            }
+            prev = q;
+        }
+        if k == net.nv() {
+            break;
+        }
+
+        // aggregate the refined partition, falling back to the unrefined one
+        // if refinement merged nothing; each aggregate vertex starts in the
+        // community that contains it.
+        let mut refined = refine(&net, &comm, config.resolution, config.randomness, &mut rng);
+        let r = renumber(&mut refined);
+        let (by, count) = if r < net.nv() { (refined, r) } else { (comm.clone(), k) };
+        let mut next = vec![0usize; count];
+        for i in 0..net.nv() {
+            next[by[i]] = comm[i];
+        }
+        node_of.iter_mut().for_each(|i| *i = by[*i]);
+        net = net.aggregate(&by, count);
+        comm = next;
+    }
+    finish(&original, levels)
+}
+
+fn weights_as_f64<V, W>(weights: fn(V, V) -> W) -> impl Fn(V, V) -> f64
+where
+    W: num::Float,
+{
+    move |u, v| weights(u, v).to_f64().expect("Invalid weight")
+}
+
+/// Louvain modularity optimization of an undirected graph: vertices are
+/// greedily moved between neighboring communities, then each community is
+/// merged into a single vertex and the process repeats on the smaller graph.
+pub fn louvain<V>(g: &impl Graph<V>, config: &CommunityConfig) -> Communities<V>
+where
+    V: PrimInt + AsPrimitive<usize>,
+    usize: AsPrimitive<V>,
+{
+    louvain_network(Network::from_graph(g, |_, _| 1.0), config)
+}
+
+/// Louvain modularity optimization with non-negative, symmetric edge weights.
+pub fn weighted_louvain<V, W>(g: &impl Graph<V>, weights: fn(V, V) -> W, config: &CommunityConfig) -> Communities<V>
+where
+    V: PrimInt + AsPrimitive<usize>,
+    usize: AsPrimitive<V>,
+    W: num::Float,
+{
+    louvain_network(Network::from_graph(g, weights_as_f64(weights)), config)
+}
+
+/// Leiden modularity optimization of an undirected graph (Traag, Waltman and
+/// van Eck, 2019). Improves on Louvain by refining every community into
+/// well-connected subcommunities before merging, so that communities are
+/// never internally disconnected.
+pub fn leiden<V>(g: &impl Graph<V>, config: &CommunityConfig) -> Communities<V>
+where
+    V: PrimInt + AsPrimitive<usize>,
+    usize: AsPrimitive<V>,
+{
+    leiden_network(Network::from_graph(g, |_, _| 1.0), config)
+}
+
+/// Leiden modularity optimization with non-negative, symmetric edge weights.
+pub fn weighted_leiden<V, W>(g: &impl Graph<V>, weights: fn(V, V) -> W, config: &CommunityConfig) -> Communities<V>
+where
+    V: PrimInt + AsPrimitive<usize>,
+    usize: AsPrimitive<V>,
+    W: num::Float,
+{
+    leiden_network(Network::from_graph(g, weights_as_f64(weights)), config)
+}
+
+fn labels_of<V: PrimInt + AsPrimitive<usize>>(partition: &[V]) -> Vec<usize> {
+    let mut labels: Vec<usize> = partition.iter().map(|c| c.as_()).collect();
+    renumber(&mut labels);
+    labels
+}
+
+/// Modularity of a partition of an undirected graph, given as the community
+/// label of every vertex. Labels need not be contiguous.
+pub fn modularity<V>(g: &impl Graph<V>, partition: &[V]) -> f64
+where
+    V: PrimInt + AsPrimitive<usize>,
+{
+    assert_eq!(partition.len(), g.nv().as_(), "partition must have one entry per vertex");
+    Network::from_graph(g, |_, _| 1.0).quality(&labels_of(partition), 1.0)
+}
+
+/// Modularity of a partition of an undirected graph with edge weights.
+pub fn weighted_modularity<V, W>(g: &impl Graph<V>, weights: fn(V, V) -> W, partition: &[V]) -> f64
+where
+    V: PrimInt + AsPrimitive<usize>,
+    W: num::Float,
+{
+    assert_eq!(partition.len(), g.nv().as_(), "partition must have one entry per vertex");
+    Network::from_graph(g, weights_as_f64(weights)).quality(&labels_of(partition), 1.0)
+}
+
+/// How vertices see each other's labels during label propagation.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LabelUpdate {
+    /// Vertices are updated in parallel in a random order, each seeing the
+    /// labels written so far. With more than one thread the result depends on
+    /// scheduling.
+    Asynchronous,
+    /// Vertices are greedily colored, and each color class, an independent
+    /// set, is updated in parallel from the labels of the previous classes
+    /// (Cordasco and Gargano, 2010). Deterministic for a given seed.
+    SemiSynchronous,
+}
+
+#[derive(Clone, Debug)]
+pub struct LabelPropagationConfig {
+    pub update: LabelUpdate,
+    /// Iteration stops once no label changes, or after this many iterations.
+    pub max_iterations: usize,
+    /// Seed for the visiting order and for breaking ties between labels.
+    pub seed: u64,
+}
+
+impl Default for LabelPropagationConfig {
+    fn default() -> Self {
+        LabelPropagationConfig {
+            update: LabelUpdate::SemiSynchronous,
+            max_iterations: 100,
+            seed: 0,
+        }
+    }
+}
+
+// the most frequent label among the neighbors of v. The current label wins
+// ties; other ties are broken by a hash of the label, v and `salt`, so that
+// the choice does not depend on the thread doing it.
+fn dominant_label<V, F>(g: &impl Graph<V>, v: V, label: F, salt: u64) -> usize
+where
+    V: PrimInt + AsPrimitive<usize>,
+    F: Fn(usize) -> usize,
+{
+    let vu = v.as_();
+    let current = label(vu);
+    let mut nbr_labels: Vec<usize> = g.out_neighbors(v).iter().filter(|u| **u != v).map(|u| label(u.as_())).collect();
+    nbr_labels.sort_unstable();
+    let key = |l: usize| mix(salt ^ mix(vu as u64) ^ (l as u64));
+    let (mut best, mut best_count) = (current, 0usize);
+    let mut i = 0;
+    while i < nbr_labels.len() {
+        let l = nbr_labels[i];
+        let mut j = i;
+        while j < nbr_labels.len() && nbr_labels[j] == l {
+            j += 1;
+        }
+        let count = j - i;
+        let better = count > best_count
+            || (count == best_count && best != current && (l == current || key(l) < key(best)));
+        if better {
+            best = l;
+            best_count = count;
+        }
+        i = j;
+    }
+    best
+}
+
+// greedy coloring in vertex order; returns the vertices of each color.
+fn color_classes<V>(g: &impl Graph<V>) -> Vec<Vec<V>>
+where
+    V: PrimInt + AsPrimitive<usize>,
+{
+    let n = g.nv().as_();
+    let mut colors = vec![usize::MAX; n];
+    let mut used: Vec<usize> = Vec::new();
+    let mut classes: Vec<Vec<V>> = Vec::new();
+    for v in g.vertices() {
+        for u in g.out_neighbors(v) {
+            let c = colors[u.as_()];
+            if c != usize::MAX {
+                used.push(c);
+            }
+        }
+        used.sort_unstable();
+        used.dedup();
+        let c = used.iter().enumerate().find(|(i, c)| *i != **c).map_or(used.len(), |(i, _)| i);
+        used.clear();
+        colors[v.as_()] = c;
+        if c == classes.len() {
+            classes.push(Vec::new());
+        }
+        classes[c].push(v);
+    }
+    classes
+}
+
+/// Label propagation community detection (Raghavan, Albert and Kumara, 2007)
+/// on an undirected graph: every vertex starts in its own community and
+/// repeatedly adopts the most frequent label among its neighbors. Returns the
+/// community of every vertex, numbered from zero, and the number of
+/// iterations performed.
+pub fn label_propagation<V, G>(g: &G, config: &LabelPropagationConfig) -> (Vec<V>, usize)
+where
+    G: Graph<V> + Sync,
+    V: PrimInt + AsPrimitive<usize> + Send + Sync,
+    usize: AsPrimitive<V>,
+{
+    let n = g.nv().as_();
+    let labels: Vec<AtomicUsize> = (0..n).map(AtomicUsize::new).collect();
+    let label = |u: usize| labels[u].load(Ordering::Relaxed);
+    let mut rng = StdRng::seed_from_u64(config.seed);
+    let classes = match config.update {
+        LabelUpdate::SemiSynchronous => color_classes(g),
+        LabelUpdate::Asynchronous => vec![],
+    };
+    let mut order: Vec<V> = g.vertices().collect();
+
+    // updates the vertices of `batch` in parallel; returns how many changed.
+    let update = |batch: &[V], salt: u64| -> usize {
+        batch.par_iter().filter(|v| {
+            let vu = v.as_();
+            let l = dominant_label(g, **v, label, salt);
+            l != labels[vu].swap(l, Ordering::Relaxed)
+        }).count()
+    };
+
+    let mut iterations = 0;
+    while iterations < config.max_iterations {
+        iterations += 1;
+        let salt = mix(config.seed ^ mix(iterations as u64));
+        let changed = match config.update {
+            LabelUpdate::Asynchronous => {
+                order.shuffle(&mut rng);
+                update(&order, salt)
+            }
+            LabelUpdate::SemiSynchronous => classes.iter().map(|c| update(c, salt)).sum(),
+        };
+        if changed == 0 {
+            break;
+        }
+    }
+    let mut result: Vec<usize> = labels.into_iter().map(|l| l.into_inner()).collect();
+    renumber(&mut result);
+    (result.into_iter().map(|l| l.as_()).collect(), iterations)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parallel::with_threads;
+    use crate::testing::random_graph;
+    use crate::{StaticGraph, Vertex};
+
+    // two 5-cliques joined by the edge 4 - 5.
+    fn two_cliques() -> StaticGraph {
+        let mut edges: Vec<(Vertex, Vertex)> = Vec::new();
+        for base in [0, 5].iter() {
+            for u in 0..5 {
+                edges.extend(((u + 1)..5).map(|v| (base + u, base + v)));
+            }
+        }
+        edges.push((4, 5));
+        StaticGraph::from_edges(10, edges)
+    }
+
+    fn assert_two_cliques(partition: &[Vertex]) {
+        assert!(partition[..5].iter().all(|c| *c == partition[0]));
+        assert!(partition[5..].iter().all(|c| *c == partition[5]));
+        assert_ne!(partition[0], partition[5]);
+    }
+
+    fn weight(u: Vertex, v: Vertex) -> f64 {
+        1.0 + ((u + v) % 3) as f64
+    }
+
+    // modularity from its definition, summing over all pairs of vertices.
+    fn brute_force_modularity(g: &StaticGraph, weights: fn(Vertex, Vertex) -> f64, partition: &[Vertex]) -> f64 {
+        let a = |u: Vertex, v: Vertex| if g.has_edge(u, v) { weights(u, v) } else { 0.0 };
+        let k: Vec<f64> = g.vertices().map(|u| g.vertices().map(|v| a(u, v)).sum()).collect();
+        let two_m: f64 = k.iter().sum();
+        let mut q = 0.0;
+        for u in g.vertices() {
+            for v in g.vertices().filter(|v| partition[*v as usize] == partition[u as usize]) {
+                q += a(u, v) - k[u as usize] * k[v as usize] / two_m;
+            }
+        }
+        q / two_m
+    }
+
+    fn unit(_: Vertex, _: Vertex) -> f64 {
+        1.0
+    }
+
+    // every community induces a connected subgraph.
+    fn assert_connected_communities(g: &StaticGraph, partition: &[Vertex]) {
+        let ncomms = *partition.iter().max().unwrap() as usize + 1;
+        for c in 0..ncomms as Vertex {
+            let members: Vec<Vertex> = g.vertices().filter(|v| partition[*v as usize] == c).collect();
+            let mut seen = vec![false; g.nv() as usize];
+            let mut stack = vec![members[0]];
+            seen[members[0] as usize] = true;
+            while let Some(v) = stack.pop() {
+                for w in g.out_neighbors(v).iter().filter(|w| partition[**w as usize] == c) {
+                    if !seen[*w as usize] {
+                        seen[*w as usize] = true;
+                        stack.push(*w);
+                    }
+                }
+            }
+            assert!(members.iter().all(|v| seen[*v as usize]));
+        }
+    }
+
+    #[test]
+    fn two_cliques_split() {
+        let g = two_cliques();
+        for comms in [louvain(&g, &Default::default()), leiden(&g, &Default::default())].iter() {
+            assert_two_cliques(&comms.partition);
+            assert_eq!(comms.levels.last(), Some(&comms.partition));
+            let q = brute_force_modularity(&g, unit, &comms.partition);
+            assert!((comms.modularity - q).abs() < 1e-9);
+            assert!((modularity(&g, &comms.partition) - q).abs() < 1e-9);
+        }
+    }
+
+    #[test]
+    fn reported_modularity_matches() {
+        for seed in 0..5 {
+            let g = random_graph(60, 200, seed);
+            let config = CommunityConfig { seed, ..Default::default() };
+            let comms = [louvain(&g, &config), leiden(&g, &config)];
+            for c in comms.iter() {
+                assert!((c.modularity - brute_force_modularity(&g, unit, &c.partition)).abs() < 1e-9);
+                assert!(c.modularity > 0.0);
+            }
+            assert_connected_communities(&g, &comms[1].partition);
+            let weighted = [weighted_louvain(&g, weight, &config), weighted_leiden(&g, weight, &config)];
+            for c in weighted.iter() {
+                let q = brute_force_modularity(&g, weight, &c.partition);
+                assert!((weighted_modularity(&g, weight, &c.partition) - q).abs() < 1e-9);
+            }
+            assert_connected_communities(&g, &weighted[1].partition);
+        }
+    }
+
+    #[test]
+    fn seeded_runs_are_deterministic() {
+        let g = random_graph(200, 800, 11);
+        let config = CommunityConfig { seed: 5, ..Default::default() };
+        assert_eq!(louvain(&g, &config).partition, louvain(&g, &config).partition);
+        assert_eq!(leiden(&g, &config).partition, leiden(&g, &config).partition);
+        assert_eq!(leiden(&g, &config).levels, leiden(&g, &config).levels);
+    }
+
+    fn tenth(_: Vertex, _: Vertex) -> f64 {
+        0.1
+    }
+
+    #[test]
+    fn symmetric_graphs_terminate() {
+        // every move between the communities of a ring or a clique ties, and
+        // inexact weights make the tied gains differ by rounding errors only.
+        let ring = StaticGraph::from_edges(30, (0..30).map(|v| (v, (v + 1) % 30)).collect());
+        let edges: Vec<(Vertex, Vertex)> = (0..12).flat_map(|u| ((u + 1)..12).map(move |v| (u, v))).collect();
+        let clique = StaticGraph::from_edges(12, edges);
+        for g in [ring, clique].iter() {
+            for seed in 0..5 {
+                let config = CommunityConfig { seed, ..Default::default() };
+                for comms in [weighted_louvain(g, tenth, &config), weighted_leiden(g, tenth, &config)].iter() {
+                    let q = brute_force_modularity(g, tenth, &comms.partition);
+                    assert!((comms.modularity - q).abs() < 1e-9);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn label_propagation_two_cliques() {
+        let g = two_cliques();
+        for update in [LabelUpdate::SemiSynchronous, LabelUpdate::Asynchronous].iter() {
+            let config = LabelPropagationConfig { update: *update, ..Default::default() };
+            let (labels, iterations) = with_threads(Some(1), || label_propagation(&g, &config)).unwrap();
+            assert_two_cliques(&labels);
+            assert!(iterations < config.max_iterations);
+            let mut ids = labels.clone();
+            ids.sort_unstable();
+            ids.dedup();
+            assert_eq!(ids, vec![0, 1]);
+        }
+    }
+
+    #[test]
+    fn semi_synchronous_is_deterministic() {
+        let g = random_graph(300, 900, 2);
+        let config = LabelPropagationConfig { seed: 9, ..Default::default() };
+        let expected = label_propagation(&g, &config);
+        for nthreads in 1..5 {
+            assert_eq!(with_threads(Some(nthreads), || label_propagation(&g, &config)).unwrap(), expected);
+        }
+        // on a single thread, the asynchronous order only depends on the seed.
+        let config = LabelPropagationConfig { update: LabelUpdate::Asynchronous, seed: 9, ..Default::default() };
+        let run = || with_threads(Some(1), || label_propagation(&g, &config)).unwrap();
+        assert_eq!(run(), run());
+    }
+}
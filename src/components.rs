@@ -0,0 +1,745 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+use bitvec::prelude as bv;
+use rayon::prelude::*;
+
+use num::cast::AsPrimitive;
+use num::traits::PrimInt;
+use crate::disjoint_set::DisjointSet;
+use crate::traits::Graph;
+use crate::{StaticDiGraph, Vertex};
+
+/// Tarjan's strongly connected components, computed iteratively. Returns a
+/// component label per vertex; components are numbered in reverse topological
+/// order of the condensation.
+pub fn strongly_connected_components<V>(g: &impl Graph<V>) -> Vec<V>
+where
+    V: PrimInt + AsPrimitive<usize>,
+    usize: AsPrimitive<V>,
+{
+    let n = g.nv().as_();
+    let unvisited = usize::MAX;
+    let mut index = vec![unvisited; n];
+    let mut lowlink = vec![0usize; n];
+    let mut on_stack: bv::BitVec<bv::Lsb0, u64> = bv::BitVec::repeat(false, n);
+    let mut labels = vec![V::max_value(); n];
+
+    let mut stack: Vec<V> = Vec::new();
+    // (vertex, position of the next neighbor to explore)
+    let mut callstack: Vec<(V, usize)> = Vec::new();
+    let mut next_index = 0usize;
+    let mut ncomps = 0usize;
+
+    for s in g.vertices() {
+        let su = s.as_();
+        if index[su] != unvisited {
+            continue;
+        }
+        index[su] = next_index;
+        lowlink[su] = next_index;
+        next_index += 1;
+        stack.push(s);
+        on_stack.set(su, true);
+        callstack.push((s, 0));
+
+        while let Some((v, i)) = callstack.pop() {
+            let vu = v.as_();
+            let nbrs = g.out_neighbors(v);
+            if i < nbrs.len() {
+                callstack.push((v, i + 1));
+                let w = nbrs[i];
+                let wu = w.as_();
+                if index[wu] == unvisited {
+                    index[wu] = next_index;
+                    lowlink[wu] = next_index;
+                    next_index += 1;
+                    stack.push(w);
+                    on_stack.set(wu, true);
+                    callstack.push((w, 0));
+                } else if on_stack[wu] && index[wu] < lowlink[vu] {
+                    lowlink[vu] = index[wu];
+                }
+                continue;
+            }
+
+            // all neighbors of v are done.
+            if lowlink[vu] == index[vu] {
+                loop {
+                    let w = stack.pop().expect("Tarjan stack underflow");
+                    let wu = w.as_();
+                    on_stack.set(wu, false);
+                    labels[wu] = ncomps.as_();
+                    if wu == vu {
+                        break;
+                    }
+                }
+                ncomps += 1;
+            }
+            if let Some(&(p, _)) = callstack.last() {
+                let pu = p.as_();
+                if lowlink[vu] < lowlink[pu] {
+                    lowlink[pu] = lowlink[vu];
+                }
+            }
+        }
+    }
+    labels
+}
+
+// vertices that have been assigned to a component no longer belong to any set.
+const DONE: usize = usize::MAX;
+
+struct FwBwTask<V> {
+    set: usize,
+    verts: Vec<V>,
+}
+
+// level-synchronous parallel search from `root`. A neighbor is visited if its
+// set can be moved atomically according to one of the `(from, to)` claims.
+fn claim_reachable<V, G>(g: &G, root: V, forward: bool, sets: &[AtomicUsize], claims: &[(usize, usize)])
+where
+    G: Graph<V> + Sync,
+    V: PrimInt + AsPrimitive<usize> + Send + Sync,
+{
+    let mut frontier = vec![root];
+    while !frontier.is_empty() {
+        frontier = frontier
+            .par_iter()
+            .flat_map(|&v| {
+                let nbrs = if forward { g.out_neighbors(v) } else { g.in_neighbors(v) };
+                nbrs.par_iter().filter(|w| {
+                    let s = &sets[w.as_()];
+                    claims.iter().any(|&(from, to)| {
+                        s.compare_exchange(from, to, Ordering::Relaxed, Ordering::Relaxed).is_ok()
+                    })
+                }).cloned()
+            })
+            .collect();
+    }
+}
+
+// removes (and labels) every vertex of the task with no in- or out-neighbors in
+// its set, repeatedly. Each of those is a trivial component.
+fn trim<V, G>(g: &G, task: &mut FwBwTask<V>, sets: &[AtomicUsize], indeg: &[AtomicUsize], outdeg: &[AtomicUsize], labels: &[AtomicUsize], ncomps: &AtomicUsize)
+where
+    G: Graph<V>,
+    V: PrimInt + AsPrimitive<usize>,
+{
+    let set = task.set;
+    let in_set = |w: &&V| sets[w.as_()].load(Ordering::Relaxed) == set;
+    let mut queue: Vec<V> = Vec::new();
+    for v in task.verts.iter() {
+        let vu = v.as_();
+        let din = g.in_neighbors(*v).iter().filter(in_set).count();
+        let dout = g.out_neighbors(*v).iter().filter(in_set).count();
+        indeg[vu].store(din, Ordering::Relaxed);
+        outdeg[vu].store(dout, Ordering::Relaxed);
+        if din == 0 || dout == 0 {
+            queue.push(*v);
+        }
+    }
+    while let Some(v) = queue.pop() {
+        let vu = v.as_();
+        if sets[vu].load(Ordering::Relaxed) != set {
+            continue;
+        }
+        sets[vu].store(DONE, Ordering::Relaxed);
+        labels[vu].store(ncomps.fetch_add(1, Ordering::Relaxed), Ordering::Relaxed);
+        for w in g.out_neighbors(v).iter().filter(in_set) {
+            if indeg[w.as_()].fetch_sub(1, Ordering::Relaxed) == 1 {
+                queue.push(*w);
+            }
+        }
+        for w in g.in_neighbors(v).iter().filter(in_set) {
+            if outdeg[w.as_()].fetch_sub(1, Ordering::Relaxed) == 1 {
+                queue.push(*w);
+            }
+        }
+    }
+    task.verts.retain(|v| sets[v.as_()].load(Ordering::Relaxed) == set);
+}
+
+/// Parallel forward-backward strongly connected components. Returns a
+/// component label per vertex. Labels are dense but, unlike
+/// `strongly_connected_components`, their order is not deterministic.
+pub fn parallel_strongly_connected_components<V, G>(g: &G) -> Vec<V>
+where
+    G: Graph<V> + Sync,
+    V: PrimInt + AsPrimitive<usize> + Send + Sync,
+    usize: AsPrimitive<V>,
+{
+    let n = g.nv().as_();
+    let new_atomics = |x: usize| -> Vec<AtomicUsize> { (0..n).map(|_| AtomicUsize::new(x)).collect() };
+    let sets = new_atomics(0);
+    let labels = new_atomics(DONE);
+    let indeg = new_atomics(0);
+    let outdeg = new_atomics(0);
+    let next_set = AtomicUsize::new(1);
+    let ncomps = AtomicUsize::new(0);
+
+    let mut tasks = vec![FwBwTask { set: 0, verts: g.vertices().collect() }];
+    while !tasks.is_empty() {
+        tasks = tasks.into_par_iter().flat_map(|mut task| {
+            trim(g, &mut task, &sets, &indeg, &outdeg, &labels, &ncomps);
+            if task.verts.is_empty() {
+                return vec![];
+            }
+            let set = task.set;
+            let fw = next_set.fetch_add(3, Ordering::Relaxed);
+            let bw = fw + 1;
+            let scc = fw + 2;
+
+            let pivot = task.verts[0];
+            sets[pivot.as_()].store(fw, Ordering::Relaxed);
+            claim_reachable(g, pivot, true, &sets, &[(set, fw)]);
+            sets[pivot.as_()].store(scc, Ordering::Relaxed);
+            claim_reachable(g, pivot, false, &sets, &[(fw, scc), (set, bw)]);
+
+            let comp = ncomps.fetch_add(1, Ordering::Relaxed);
+            let mut fw_verts: Vec<V> = Vec::new();
+            let mut bw_verts: Vec<V> = Vec::new();
+            let mut rest: Vec<V> = Vec::new();
+            for v in task.verts {
+                let vu = v.as_();
+                let s = sets[vu].load(Ordering::Relaxed);
+                if s == scc {
+                    sets[vu].store(DONE, Ordering::Relaxed);
+                    labels[vu].store(comp, Ordering::Relaxed);
+                } else if s == fw {
+                    fw_verts.push(v);
+                } else if s == bw {
+                    bw_verts.push(v);
+                } else {
+                    rest.push(v);
+                }
+            }
+            vec![
+                FwBwTask { set: fw, verts: fw_verts },
+                FwBwTask { set: bw, verts: bw_verts },
+                FwBwTask { set, verts: rest },
+            ]
+        }).filter(|task| !task.verts.is_empty()).collect();
+    }
+    labels.into_iter().map(|l| l.into_inner().as_()).collect()
+}
+
+/// Returns the condensation of `g`: a DAG with one vertex per strongly
+/// connected component, as labeled by `labels`.
+pub fn condensation(g: &StaticDiGraph, labels: &[Vertex]) -> StaticDiGraph {
+    let ncomps = labels.iter().max().map_or(0, |&m| m + 1);
+    let edgelist: Vec<(Vertex, Vertex)> = g
+        .vertices()
+        .flat_map(|u| {
+            let cu = labels[u as usize];
+            g.out_neighbors(u).iter().map(move |v| (cu, labels[*v as usize]))
+        })
+        .filter(|(cu, cv)| cu != cv)
+        .collect();
+    StaticDiGraph::from_edges(ncomps, edgelist)
+}
+
+// renumbers components, given by a representative per vertex, densely in order
+// of their lowest vertex, and counts their sizes.
+fn relabel<V>(reps: impl Iterator<Item = usize>, n: usize) -> (Vec<V>, Vec<usize>)
+where
+    V: PrimInt + AsPrimitive<usize>,
+    usize: AsPrimitive<V>,
+{
+    let mut newlabel = vec![usize::MAX; n];
+    let mut labels: Vec<V> = Vec::with_capacity(n);
+    let mut sizes: Vec<usize> = Vec::new();
+    for r in reps {
+        if newlabel[r] == usize::MAX {
+            newlabel[r] = sizes.len();
+            sizes.push(0);
+        }
+        sizes[newlabel[r]] += 1;
+        labels.push(newlabel[r].as_());
+    }
+    (labels, sizes)
+}
+
+/// Connected components of an undirected graph by breadth-first search.
+/// Returns a component label per vertex and the size of each component.
+pub fn connected_components<V>(g: &impl Graph<V>) -> (Vec<V>, Vec<usize>)
+where
+    V: PrimInt + AsPrimitive<usize>,
+    usize: AsPrimitive<V>,
+{
+    let n = g.nv().as_();
+    let mut labels = vec![V::max_value(); n];
+    let mut sizes: Vec<usize> = Vec::new();
+    let mut queue: Vec<V> = Vec::new();
+    for s in g.vertices() {
+        if labels[s.as_()] != V::max_value() {
+            continue;
+        }
+        let comp: V = sizes.len().as_();
+        labels[s.as_()] = comp;
+        queue.clear();
+        queue.push(s);
+        let mut head = 0;
+        while head < queue.len() {
+            let v = queue[head];
+            head += 1;
+            for w in g.out_neighbors(v) {
+                let wu = w.as_();
+                if labels[wu] == V::max_value() {
+                    labels[wu] = comp;
+                    queue.push(*w);
+                }
+            }
+        }
+        sizes.push(queue.len());
+    }
+    (labels, sizes)
+}
+
+/// Weakly connected components of a directed graph, using a disjoint set.
+/// Returns a component label per vertex and the size of each component.
+pub fn weakly_connected_components<V>(g: &impl Graph<V>) -> (Vec<V>, Vec<usize>)
+where
+    V: PrimInt + AsPrimitive<usize>,
+    usize: AsPrimitive<V>,
+{
+    let mut ds = DisjointSet::new(g.nv());
+    for u in g.vertices() {
+        for v in g.out_neighbors(u) {
+            ds.union(u, *v);
+        }
+    }
+    relabel(g.vertices().map(|v| ds.find(v).as_()), g.nv().as_())
+}
+
+// number of neighbors sampled per vertex before the largest component is found.
+const AFFOREST_ROUNDS: usize = 2;
+const AFFOREST_SAMPLES: usize = 1024;
+
+fn afforest_link(u: usize, v: usize, comp: &[AtomicUsize]) {
+    let mut p1 = comp[u].load(Ordering::Relaxed);
+    let mut p2 = comp[v].load(Ordering::Relaxed);
+    while p1 != p2 {
+        let (high, low) = if p1 > p2 { (p1, p2) } else { (p2, p1) };
+        let p_high = comp[high].load(Ordering::Relaxed);
+        if p_high == low {
+            break;
+        }
+        if p_high == high && comp[high].compare_exchange(high, low, Ordering::Relaxed, Ordering::Relaxed).is_ok() {
+            break;
+        }
+        p1 = comp[comp[high].load(Ordering::Relaxed)].load(Ordering::Relaxed);
+        p2 = comp[low].load(Ordering::Relaxed);
+    }
+}
+
+fn afforest_compress(comp: &[AtomicUsize]) {
+    (0..comp.len()).into_par_iter().for_each(|v| {
+        loop {
+            let p = comp[v].load(Ordering::Relaxed);
+            let gp = comp[p].load(Ordering::Relaxed);
+            if p == gp {
+                break;
+            }
+            comp[v].store(gp, Ordering::Relaxed);
+        }
+    });
+}
+
+// Afforest (Sutton et al., 2018): link a few neighbors of every vertex, then
+// skip the vertices already in the largest intermediate component when linking
+// the remaining edges. For directed graphs the in-edges are linked as well.
+fn afforest<V, G>(g: &G, directed: bool) -> (Vec<V>, Vec<usize>)
+where
+    G: Graph<V> + Sync,
+    V: PrimInt + AsPrimitive<usize> + Send + Sync,
+    usize: AsPrimitive<V>,
+{
+    let n = g.nv().as_();
+    let comp: Vec<AtomicUsize> = (0..n).map(AtomicUsize::new).collect();
+    for r in 0..AFFOREST_ROUNDS {
+        (0..n).into_par_iter().for_each(|u| {
+            if let Some(v) = g.out_neighbors(u.as_()).get(r) {
+                afforest_link(u, v.as_(), &comp);
+            }
+        });
+        afforest_compress(&comp);
+    }
+
+    let mut largest = 0;
+    if n > 0 {
+        let stride = std::cmp::max(n / AFFOREST_SAMPLES, 1);
+        let mut samples: Vec<usize> = (0..n).step_by(stride).map(|v| comp[v].load(Ordering::Relaxed)).collect();
+        samples.sort_unstable();
+        let mut best = 0;
+        let mut i = 0;
+        while i < samples.len() {
+            let j = i + samples[i..].iter().take_while(|&&c| c == samples[i]).count();
+            if j - i > best {
+                best = j - i;
+                largest = samples[i];
+            }
+            i = j;
+        }
+    }
+
+    (0..n).into_par_iter().for_each(|u| {
+        if comp[u].load(Ordering::Relaxed) == largest {
+            return;
+        }
+        let uv: V = u.as_();
+        for v in g.out_neighbors(uv).iter().skip(AFFOREST_ROUNDS) {
+            afforest_link(u, v.as_(), &comp);
+        }
+        if directed {
+            for v in g.in_neighbors(uv) {
+                afforest_link(u, v.as_(), &comp);
+            }
+        }
+    });
+    afforest_compress(&comp);
+    relabel(comp.into_iter().map(|c| c.into_inner()), n)
+}
+
+/// Parallel connected components of an undirected graph using Afforest.
+/// Labels and sizes match `connected_components`.
+pub fn parallel_connected_components<V, G>(g: &G) -> (Vec<V>, Vec<usize>)
+where
+    G: Graph<V> + Sync,
+    V: PrimInt + AsPrimitive<usize> + Send + Sync,
+    usize: AsPrimitive<V>,
+{
+    afforest(g, false)
+}
+
+/// Parallel weakly connected components of a directed graph using Afforest.
+/// Labels and sizes match `weakly_connected_components`.
+pub fn parallel_weakly_connected_components<V, G>(g: &G) -> (Vec<V>, Vec<usize>)
+where
+    G: Graph<V> + Sync,
+    V: PrimInt + AsPrimitive<usize> + Send + Sync,
+    usize: AsPrimitive<V>,
+{
+    afforest(g, true)
+}
+
+pub struct BiconnectedComponents<V> {
+    /// Articulation points, in ascending order.
+    pub articulation_points: Vec<V>,
+    /// Bridges as `(u, v)` with `u < v`, in ascending order.
+    pub bridges: Vec<(V, V)>,
+    /// Biconnected component of each edge in CSR edge order (see
+    /// `Graph::edge_offsets`). Both directions of an edge share the same
+    /// component; self-loops belong to none and are `usize::MAX`.
+    pub edge_components: Vec<usize>,
+    pub ncomponents: usize,
+}
+
+/// Hopcroft-Tarjan biconnected components of an undirected graph, computed by
+/// iterative depth-first search.
+pub fn biconnected_components<V>(g: &impl Graph<V>) -> BiconnectedComponents<V>
+where
+    V: PrimInt + AsPrimitive<usize>,
+{
+    let n = g.nv().as_();
+    let offsets = g.edge_offsets();
+    let unvisited = usize::MAX;
+    let mut disc = vec![unvisited; n];
+    let mut low = vec![0usize; n];
+    let mut parents = vec![V::max_value(); n];
+    let mut parent_edge = vec![usize::MAX; n];
+    let mut is_articulation: bv::BitVec<bv::Lsb0, u64> = bv::BitVec::repeat(false, n);
+    let mut edge_components = vec![usize::MAX; g.ne()];
+    let mut bridges: Vec<(V, V)> = Vec::new();
+    let mut ncomponents = 0usize;
+
+    let mut edgestack: Vec<usize> = Vec::new();
+    let mut callstack: Vec<(V, usize)> = Vec::new();
+    let mut time = 0usize;
+
+    for root in g.vertices() {
+        let ru = root.as_();
+        if disc[ru] != unvisited {
+            continue;
+        }
+        disc[ru] = time;
+        low[ru] = time;
+        time += 1;
+        let mut root_children = 0usize;
+        callstack.push((root, 0));
+
+        while let Some((v, i)) = callstack.pop() {
+            let vu = v.as_();
+            let nbrs = g.out_neighbors(v);
+            if i < nbrs.len() {
+                callstack.push((v, i + 1));
+                let w = nbrs[i];
+                let wu = w.as_();
+                let e = offsets[vu] + i;
+                if disc[wu] == unvisited {
+                    disc[wu] = time;
+                    low[wu] = time;
+                    time += 1;
+                    parents[wu] = v;
+                    parent_edge[wu] = e;
+                    edgestack.push(e);
+                    callstack.push((w, 0));
+                    if v == root {
+                        root_children += 1;
+                    }
+                } else if disc[wu] < disc[vu] && w != parents[vu] {
+                    // back edge to an ancestor other than the parent.
+                    if disc[wu] < low[vu] {
+                        low[vu] = disc[wu];
+                    }
+                    edgestack.push(e);
+                }
+                continue;
+            }
+
+            // all neighbors of v are done.
+            let p = match callstack.last() {
+                Some(&(p, _)) => p,
+                None => break,
+            };
+            let pu = p.as_();
+            if low[vu] < low[pu] {
+                low[pu] = low[vu];
+            }
+            if low[vu] >= disc[pu] {
+                if p != root {
+                    is_articulation.set(pu, true);
+                }
+                if low[vu] > disc[pu] {
+                    bridges.push(if p < v { (p, v) } else { (v, p) });
+                }
+                loop {
+                    let e = edgestack.pop().expect("edge stack underflow");
+                    edge_components[e] = ncomponents;
+                    if e == parent_edge[vu] {
+                        break;
+                    }
+                }
+                ncomponents += 1;
+            }
+        }
+        if root_children > 1 {
+            is_articulation.set(ru, true);
+        }
+    }
+
+    // label the reverse direction of every edge.
+    for u in g.vertices() {
+        let uu = u.as_();
+        for (i, w) in g.out_neighbors(u).iter().enumerate() {
+            let e = offsets[uu] + i;
+            if edge_components[e] == usize::MAX {
+                continue;
+            }
+            let wu = w.as_();
+            if let Ok(j) = g.out_neighbors(*w).binary_search(&u) {
+                edge_components[offsets[wu] + j] = edge_components[e];
+            }
+        }
+    }
+
+    bridges.sort_unstable();
+    let articulation_points = g.vertices().filter(|v| is_articulation[v.as_()]).collect();
+    BiconnectedComponents { articulation_points, bridges, edge_components, ncomponents }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{random_digraph, random_graph};
+    use crate::StaticGraph;
+
+    // whether two labelings induce the same partition of the vertices.
+    fn same_partition<V: PrimInt + AsPrimitive<usize>>(a: &[V], b: &[V]) -> bool {
+        a.len() == b.len() && (0..a.len()).all(|u| (0..a.len()).all(|v| (a[u] == a[v]) == (b[u] == b[v])))
+    }
+
+    // strongly connected components by mutual reachability.
+    fn brute_force_scc(g: &StaticDiGraph) -> Vec<Vertex> {
+        let n = g.nv() as usize;
+        let reach: Vec<Vec<bool>> = g.vertices().map(|s| {
+            let mut seen = vec![false; n];
+            let mut stack = vec![s];
+            seen[s as usize] = true;
+            while let Some(v) = stack.pop() {
+                for w in g.out_neighbors(v) {
+                    if !seen[*w as usize] {
+                        seen[*w as usize] = true;
+                        stack.push(*w);
+                    }
+                }
+            }
+            seen
+        }).collect();
+        (0..n).map(|u| (0..n).find(|v| reach[u][*v] && reach[*v][u]).unwrap() as Vertex).collect()
+    }
+
+    fn check_scc(g: &StaticDiGraph) {
+        let expected = brute_force_scc(g);
+        let tarjan = strongly_connected_components(g);
+        let fwbw = parallel_strongly_connected_components(g);
+        assert!(same_partition(&tarjan, &expected));
+        assert!(same_partition(&fwbw, &expected));
+        let ncomps = expected.iter().enumerate().filter(|(v, c)| **c as usize == *v).count();
+        for labels in [&tarjan, &fwbw].iter() {
+            let mut sorted = labels.to_vec();
+            sorted.sort_unstable();
+            sorted.dedup();
+            assert_eq!(sorted, (0..ncomps as Vertex).collect::<Vec<_>>());
+        }
+        // reverse topological order of the condensation.
+        for u in g.vertices() {
+            for v in g.out_neighbors(u) {
+                assert!(tarjan[u as usize] >= tarjan[*v as usize]);
+            }
+        }
+        let dag = condensation(g, &tarjan);
+        assert_eq!(dag.nv() as usize, ncomps);
+        assert!(dag.vertices().all(|c| dag.out_neighbors(c).iter().all(|d| *d < c)));
+    }
+
+    #[test]
+    fn scc_small_graphs() {
+        // two cycles joined by an edge, a self-loop and an isolated vertex.
+        let g = StaticDiGraph::from_edges(8, vec![(0, 1), (1, 2), (2, 0), (2, 3), (3, 4), (4, 3), (5, 5), (4, 5)]);
+        assert_eq!(g.nv(), 8);
+        assert_eq!(g.out_neighbors(7), &[] as &[Vertex]);
+        check_scc(&g);
+        assert_eq!(strongly_connected_components(&g).iter().max(), Some(&4));
+
+        check_scc(&StaticDiGraph::from_edges(0, vec![]));
+        check_scc(&StaticDiGraph::from_edges(5, vec![]));
+        check_scc(&StaticDiGraph::from_edges(3, vec![(0, 0), (1, 1), (2, 2), (0, 1), (1, 2)]));
+        // a single long cycle.
+        check_scc(&StaticDiGraph::from_edges(50, (0..50).map(|v| (v, (v + 1) % 50)).collect()));
+    }
+
+    #[test]
+    fn scc_random_graphs() {
+        for seed in 0..20 {
+            check_scc(&random_digraph(40, 30 + 3 * seed as usize, seed));
+        }
+    }
+
+    #[test]
+    fn from_edges_dedups_and_keeps_isolated_vertices() {
+        let g = StaticDiGraph::from_edges(6, vec![(2, 1), (0, 1), (2, 1), (1, 1)]);
+        assert_eq!((g.nv(), g.ne()), (6, 3));
+        assert_eq!(g.out_neighbors(2), &[1]);
+        assert_eq!(g.in_neighbors(1), &[0, 1, 2]);
+        assert!(g.has_edge(0, 1) && !g.has_edge(1, 0));
+        assert_eq!(g.out_degree(5), 0);
+
+        let h = StaticGraph::from_edges(4, vec![(1, 0), (0, 1), (2, 2)]);
+        assert_eq!((h.nv(), h.ne()), (4, 3));
+        assert_eq!(h.out_neighbors(0), &[1]);
+        assert_eq!(h.out_neighbors(2), &[2]);
+        assert_eq!(h.out_degree(3), 0);
+    }
+
+    #[test]
+    #[should_panic(expected = "outside of 0..3")]
+    fn from_edges_rejects_out_of_range_endpoints() {
+        StaticDiGraph::from_edges(3, vec![(0, 1), (1, 3)]);
+    }
+
+    fn check_components(g: &StaticGraph) {
+        let (labels, sizes) = connected_components(g);
+        assert!(g.vertices().all(|u| g.out_neighbors(u).iter().all(|v| labels[u as usize] == labels[*v as usize])));
+        assert_eq!(sizes.iter().sum::<usize>(), g.nv() as usize);
+        assert_eq!(parallel_connected_components(g), (labels.clone(), sizes.clone()));
+        // the union-find labels of an undirected graph.
+        assert_eq!(weakly_connected_components(g), (labels, sizes));
+    }
+
+    #[test]
+    fn connected_components_agree() {
+        let g = StaticGraph::from_edges(7, vec![(0, 1), (1, 2), (3, 4), (5, 5)]);
+        let (labels, sizes) = connected_components(&g);
+        assert_eq!(labels, vec![0, 0, 0, 1, 1, 2, 3]);
+        assert_eq!(sizes, vec![3, 2, 1, 1]);
+        check_components(&g);
+        check_components(&StaticGraph::from_edges(0, vec![]));
+        // sparse enough for many components, large enough to sample.
+        for (seed, m) in [(1, 1000), (2, 1500), (3, 2500), (4, 6000)].iter() {
+            check_components(&random_graph(3000, *m, *seed));
+        }
+    }
+
+    #[test]
+    fn weakly_connected_components_agree() {
+        for seed in 0..10 {
+            let g = random_digraph(2000, 800 + 100 * seed as usize, seed);
+            let (labels, sizes) = weakly_connected_components(&g);
+            let edges = g.vertices().flat_map(|u| g.out_neighbors(u).iter().map(move |v| (u, *v))).collect();
+            let undirected = StaticGraph::from_edges(g.nv(), edges);
+            assert_eq!(connected_components(&undirected), (labels.clone(), sizes.clone()));
+            assert_eq!(parallel_weakly_connected_components(&g), (labels, sizes));
+        }
+    }
+
+    // the edges (u, v) with u < v of every biconnected component, sorted.
+    fn component_edges(g: &StaticGraph, bcc: &BiconnectedComponents<Vertex>) -> Vec<Vec<(Vertex, Vertex)>> {
+        let offsets = g.edge_offsets();
+        let mut comps = vec![Vec::new(); bcc.ncomponents];
+        for u in g.vertices() {
+            for (i, v) in g.out_neighbors(u).iter().enumerate() {
+                let c = bcc.edge_components[offsets[u as usize] + i];
+                if u == *v {
+                    assert_eq!(c, usize::MAX);
+                    continue;
+                }
+                let j = g.out_neighbors(*v).binary_search(&u).unwrap();
+                assert_eq!(c, bcc.edge_components[offsets[*v as usize] + j]);
+                if u < *v {
+                    comps[c].push((u, *v));
+                }
+            }
+        }
+        comps.sort();
+        comps
+    }
+
+    #[test]
+    fn biconnected_path_and_cycle() {
+        let path = StaticGraph::from_edges(4, vec![(0, 1), (1, 2), (2, 3)]);
+        let bcc = biconnected_components(&path);
+        assert_eq!(bcc.articulation_points, vec![1, 2]);
+        assert_eq!(bcc.bridges, vec![(0, 1), (1, 2), (2, 3)]);
+        assert_eq!(component_edges(&path, &bcc), vec![vec![(0, 1)], vec![(1, 2)], vec![(2, 3)]]);
+
+        let cycle = StaticGraph::from_edges(5, (0..5).map(|v| (v, (v + 1) % 5)).collect());
+        let bcc = biconnected_components(&cycle);
+        assert!(bcc.articulation_points.is_empty() && bcc.bridges.is_empty());
+        assert_eq!(bcc.ncomponents, 1);
+
+        // isolated vertices and self-loops belong to no component.
+        let g = StaticGraph::from_edges(3, vec![(0, 0), (1, 2)]);
+        let bcc = biconnected_components(&g);
+        assert_eq!(bcc.bridges, vec![(1, 2)]);
+        assert_eq!(component_edges(&g, &bcc), vec![vec![(1, 2)]]);
+    }
+
+    #[test]
+    fn biconnected_triangles() {
+        // bowtie: two triangles sharing vertex 2.
+        let bowtie = StaticGraph::from_edges(5, vec![(0, 1), (1, 2), (2, 0), (2, 3), (3, 4), (4, 2)]);
+        let bcc = biconnected_components(&bowtie);
+        assert_eq!(bcc.articulation_points, vec![2]);
+        assert!(bcc.bridges.is_empty());
+        assert_eq!(component_edges(&bowtie, &bcc), vec![vec![(0, 1), (0, 2), (1, 2)], vec![(2, 3), (2, 4), (3, 4)]]);
+
+        // two triangles joined through the cut vertex 3.
+        let g = StaticGraph::from_edges(7, vec![(0, 1), (1, 2), (2, 0), (2, 3), (3, 4), (4, 5), (5, 6), (6, 4)]);
+        let bcc = biconnected_components(&g);
+        assert_eq!(bcc.articulation_points, vec![2, 3, 4]);
+        assert_eq!(bcc.bridges, vec![(2, 3), (3, 4)]);
+        assert_eq!(
+            component_edges(&g, &bcc),
+            vec![vec![(0, 1), (0, 2), (1, 2)], vec![(2, 3)], vec![(3, 4)], vec![(4, 5), (4, 6), (5, 6)]]
+        );
+    }
+}
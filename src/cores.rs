@@ -0,0 +1,191 @@
+use num::cast::AsPrimitive;
+use num::traits::PrimInt;
+use crate::traits::Graph;
+use crate::{StaticGraph, Vertex};
+
+/// Batagelj-Zaversnik bucket-based k-core decomposition in O(m). Returns the
+/// core number of every vertex, and the degeneracy ordering: the order in which
+/// vertices are peeled, each having at most `degeneracy` neighbors after it.
+/// Self-loops are ignored.
+pub fn core_decomposition<V>(g: &impl Graph<V>) -> (Vec<V>, Vec<V>)
+where
+    V: PrimInt + AsPrimitive<usize>,
+    usize: AsPrimitive<V>,
+{
+    let n = g.nv().as_();
+    let mut degrees: Vec<usize> = g.vertices()
+        .map(|v| g.out_neighbors(v).iter().filter(|u| **u != v).count())
+        .collect();
+    let maxdeg = degrees.iter().cloned().max().unwrap_or(0);
+
+    // bins[d] is the position in `order` of the first vertex of degree d.
+    let mut bins = vec![0usize; maxdeg + 1];
+    for d in degrees.iter() {
+        bins[*d] += 1;
+    }
+    let mut start = 0;
+    for b in bins.iter_mut() {
+        let count = *b;
+        *b = start;
+        start += count;
+    }
+    let mut pos = vec![0usize; n];
+    let mut order: Vec<V> = vec![V::zero(); n];
+    for v in g.vertices() {
+        let vu = v.as_();
+        let d = degrees[vu];
+        pos[vu] = bins[d];
+        order[pos[vu]] = v;
+        bins[d] += 1;
+    }
+    for d in (1..=maxdeg).rev() {
+        bins[d] = bins[d - 1];
+    }
+    bins[0] = 0;
+
+    for i in 0..n {
+        let v = order[i];
+        let dv = degrees[v.as_()];
+        for u in g.out_neighbors(v).iter().filter(|u| **u != v) {
+            let uu = u.as_();
+            let du = degrees[uu];
+            if du > dv {
+                // move u to the front of its bin, then shrink the bin.
+                let pu = pos[uu];
+                let pw = bins[du];
+                let w = order[pw];
+                if *u != w {
+                    pos[uu] = pw;
+                    pos[w.as_()] = pu;
+                    order[pu] = w;
+                    order[pw] = *u;
+                }
+                bins[du] += 1;
+                degrees[uu] -= 1;
+            }
+        }
+    }
+    (degrees.into_iter().map(|d| d.as_()).collect(), order)
+}
+
+/// Core number of every vertex.
+pub fn core_numbers<V>(g: &impl Graph<V>) -> Vec<V>
+where
+    V: PrimInt + AsPrimitive<usize>,
+    usize: AsPrimitive<V>,
+{
+    core_decomposition(g).0
+}
+
+/// The largest k for which the graph has a non-empty k-core.
+pub fn degeneracy<V>(g: &impl Graph<V>) -> V
+where
+    V: PrimInt + AsPrimitive<usize>,
+    usize: AsPrimitive<V>,
+{
+    core_numbers(g).into_iter().max().unwrap_or_else(V::zero)
+}
+
+/// Vertices in degeneracy order.
+pub fn degeneracy_ordering<V>(g: &impl Graph<V>) -> Vec<V>
+where
+    V: PrimInt + AsPrimitive<usize>,
+    usize: AsPrimitive<V>,
+{
+    core_decomposition(g).1
+}
+
+/// Returns the k-core of `g`, the maximal subgraph in which every vertex has
+/// degree at least `k`, together with the original id of each of its vertices.
+pub fn k_core(g: &StaticGraph, k: Vertex) -> (StaticGraph, Vec<Vertex>) {
+    let cores = core_numbers(g);
+    let vmap: Vec<Vertex> = g.vertices().filter(|v| cores[*v as usize] >= k).collect();
+    let mut newids = vec![Vertex::MAX; g.nv() as usize];
+    for (i, v) in vmap.iter().enumerate() {
+        newids[*v as usize] = i as Vertex;
+    }
+    let mut edgelist: Vec<(Vertex, Vertex)> = Vec::new();
+    for u in vmap.iter() {
+        for v in g.out_neighbors(*u).iter().filter(|v| **v >= *u && cores[**v as usize] >= k) {
+            edgelist.push((newids[*u as usize], newids[*v as usize]));
+        }
+    }
+    (StaticGraph::from_edges(vmap.len() as Vertex, edgelist), vmap)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::random_graph;
+
+    // core numbers by repeatedly removing the vertices of degree below k.
+    fn brute_force_cores(g: &StaticGraph) -> Vec<Vertex> {
+        let n = g.nv() as usize;
+        let mut cores = vec![0; n];
+        let mut alive = vec![true; n];
+        for k in 1..=n {
+            loop {
+                let low: Vec<Vertex> = g.vertices()
+                    .filter(|v| alive[*v as usize])
+                    .filter(|v| g.out_neighbors(*v).iter().filter(|u| **u != *v && alive[**u as usize]).count() < k)
+                    .collect();
+                if low.is_empty() {
+                    break;
+                }
+                low.iter().for_each(|v| alive[*v as usize] = false);
+            }
+            g.vertices().filter(|v| alive[*v as usize]).for_each(|v| cores[v as usize] = k as Vertex);
+        }
+        cores
+    }
+
+    fn check(g: &StaticGraph) {
+        let (cores, order) = core_decomposition(g);
+        assert_eq!(cores, brute_force_cores(g));
+        let mut sorted = order.clone();
+        sorted.sort_unstable();
+        assert_eq!(sorted, g.vertices().collect::<Vec<_>>());
+        let mut pos = vec![0; order.len()];
+        for (i, v) in order.iter().enumerate() {
+            pos[*v as usize] = i;
+        }
+        // every vertex has at most its core number of later neighbors.
+        for v in g.vertices() {
+            let later = g.out_neighbors(v).iter().filter(|u| pos[**u as usize] > pos[v as usize]).count();
+            assert!(later <= cores[v as usize] as usize);
+        }
+    }
+
+    #[test]
+    fn clique_with_tail() {
+        // K4 on 0..4, then the path 3 - 4 - 5 and an isolated vertex.
+        let mut edges: Vec<(Vertex, Vertex)> = (0..4).flat_map(|u| ((u + 1)..4).map(move |v| (u, v))).collect();
+        edges.extend(vec![(3, 4), (4, 5)]);
+        let g = StaticGraph::from_edges(7, edges);
+        assert_eq!(core_numbers(&g), vec![3, 3, 3, 3, 1, 1, 0]);
+        assert_eq!(degeneracy(&g), 3);
+        let order = degeneracy_ordering(&g);
+        assert_eq!(order[0], 6);
+        assert!(order[3..].iter().all(|v| *v < 4));
+        check(&g);
+
+        let (core, vmap) = k_core(&g, 2);
+        assert_eq!(vmap, vec![0, 1, 2, 3]);
+        assert_eq!(core.ne(), 12);
+    }
+
+    #[test]
+    fn self_loops_are_ignored() {
+        let g = StaticGraph::from_edges(5, vec![(0, 0), (1, 1), (2, 2), (0, 1), (1, 2), (2, 0), (3, 3), (3, 4)]);
+        assert_eq!(core_numbers(&g), vec![2, 2, 2, 1, 1]);
+        check(&g);
+        assert_eq!(core_numbers(&StaticGraph::from_edges(1, vec![(0, 0)])), vec![0]);
+    }
+
+    #[test]
+    fn random_graphs() {
+        for seed in 0..10 {
+            check(&random_graph(60, 100 + 20 * seed as usize, seed));
+        }
+    }
+}
@@ -0,0 +1,113 @@
+use num::cast::AsPrimitive;
+use num::traits::PrimInt;
+
+/// A union-find structure over the elements `0..n`, with union by rank and
+/// path halving.
+pub struct DisjointSet<V> {
+    parents: Vec<V>,
+    ranks: Vec<u8>,
+    nsets: usize,
+}
+
+impl<V> DisjointSet<V>
+where
+    V: PrimInt + AsPrimitive<usize>,
+    usize: AsPrimitive<V>,
+{
+    pub fn new(n: V) -> Self {
+        let nu = n.as_();
+        DisjointSet {
+            parents: (0..nu).map(|x| x.as_()).collect(),
+            ranks: vec![0; nu],
+            nsets: nu,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.parents.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.parents.is_empty()
+    }
+
+    /// The number of disjoint sets.
+    pub fn nsets(&self) -> usize {
+        self.nsets
+    }
+
+    /// Returns the representative of the set containing `x`.
+    pub fn find(&mut self, x: V) -> V {
+        let mut x = x;
+        loop {
+            let p = self.parents[x.as_()];
+            if p == x {
+                return x;
+            }
+            let gp = self.parents[p.as_()];
+            self.parents[x.as_()] = gp;
+            x = gp;
+        }
+    }
+
+    /// Merges the sets containing `x` and `y`. Returns false if they were
+    /// already in the same set.
+    pub fn union(&mut self, x: V, y: V) -> bool {
+        let rx = self.find(x);
+        let ry = self.find(y);
+        if rx == ry {
+            return false;
+        }
+        let (rxu, ryu) = (rx.as_(), ry.as_());
+        if self.ranks[rxu] < self.ranks[ryu] {
+            self.parents[rxu] = ry;
+        } else {
+            self.parents[ryu] = rx;
+            if self.ranks[rxu] == self.ranks[ryu] {
+                self.ranks[rxu] += 1;
+            }
+        }
+        self.nsets -= 1;
+        true
+    }
+
+    pub fn same_set(&mut self, x: V, y: V) -> bool {
+        self.find(x) == self.find(y)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn union_find() {
+        let mut ds = DisjointSet::<u32>::new(8);
+        assert_eq!((ds.len(), ds.nsets()), (8, 8));
+        assert!((0..8).all(|x| ds.find(x) == x));
+        assert!(ds.union(0, 1));
+        assert!(ds.union(2, 3));
+        assert!(ds.union(1, 3));
+        assert!(!ds.union(0, 2));
+        assert!(!ds.union(4, 4));
+        assert!(ds.union(5, 6));
+        assert_eq!(ds.nsets(), 4);
+        let r = ds.find(0);
+        assert!((0..4).all(|x| ds.find(x) == r));
+        assert!(ds.same_set(5, 6) && !ds.same_set(4, 5) && !ds.same_set(3, 7));
+        assert_ne!(ds.find(5), r);
+    }
+
+    #[test]
+    fn long_chains() {
+        let n = 1000usize;
+        let mut ds = DisjointSet::<usize>::new(n);
+        for x in 1..n {
+            assert!(ds.union(x - 1, x));
+        }
+        assert_eq!(ds.nsets(), 1);
+        let r = ds.find(n - 1);
+        assert!((0..n).all(|x| ds.find(x) == r));
+        assert!(DisjointSet::<u8>::new(0).is_empty());
+    }
+}
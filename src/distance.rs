@@ -0,0 +1,206 @@
+use num::cast::AsPrimitive;
+use num::traits::PrimInt;
+use crate::traits::Graph;
+use crate::traversals::{bfs, reverse_bfs};
+
+// eccentricities and distances of unreachable vertices.
+const INF: usize = usize::MAX;
+
+fn to_dist<V: PrimInt + AsPrimitive<usize>>(level: V) -> usize {
+    if level == V::max_value() { INF } else { level.as_() }
+}
+
+fn to_level<V: PrimInt + 'static>(dist: usize) -> V
+where
+    usize: AsPrimitive<V>,
+{
+    if dist == INF { V::max_value() } else { dist.as_() }
+}
+
+fn eccentricity_from_levels<V: PrimInt + AsPrimitive<usize>>(levels: &[V]) -> usize {
+    levels.iter().map(|l| to_dist(*l)).max().unwrap_or(0)
+}
+
+pub struct DistanceMeasures<V> {
+    /// Largest eccentricity, or `V::max_value()` if some vertex cannot reach
+    /// every other one.
+    pub diameter: V,
+    /// Smallest eccentricity, or `V::max_value()` if no vertex reaches every
+    /// other one.
+    pub radius: V,
+    /// Vertices whose eccentricity is the radius, in ascending order.
+    pub center: Vec<V>,
+    /// Vertices whose eccentricity is the diameter, in ascending order.
+    pub periphery: Vec<V>,
+}
+
+/// Exact diameter, radius, center and periphery using the bounding algorithm
+/// of Takes and Kosters (2011). Eccentricities are measured along out-edges;
+/// each step runs `bfs` and `reverse_bfs` from one vertex and tightens the
+/// eccentricity bounds of all others, until every vertex either has a known
+/// eccentricity or cannot be in the center or periphery.
+pub fn distance_measures<V>(g: &impl Graph<V>) -> DistanceMeasures<V>
+where
+    V: PrimInt + AsPrimitive<usize>,
+    usize: AsPrimitive<V>,
+{
+    let n = g.nv().as_();
+    if n == 0 {
+        return DistanceMeasures { diameter: V::zero(), radius: V::zero(), center: vec![], periphery: vec![] };
+    }
+    let mut lower = vec![0usize; n];
+    let mut upper = vec![INF; n];
+    let mut candidates: Vec<V> = g.vertices().collect();
+    let degree = |v: V| -> usize { g.out_degree(v).as_() + g.in_degree(v).as_() };
+
+    let mut pick_upper = true;
+    let mut w = candidates.iter().cloned().max_by_key(|v| degree(*v)).expect("Invalid candidates");
+    loop {
+        let wu = w.as_();
+        let from_w = bfs(g, w);
+        let to_w = reverse_bfs(g, w);
+        let ecc = eccentricity_from_levels(&from_w);
+        lower[wu] = ecc;
+        upper[wu] = ecc;
+
+        for v in candidates.iter() {
+            let vu = v.as_();
+            let d_from = to_dist(from_w[vu]);
+            let d_to = to_dist(to_w[vu]);
+            // ecc(v) >= d(v, w) and ecc(w) <= d(w, v) + ecc(v).
+            let mut lo = std::cmp::max(lower[vu], d_to);
+            if d_from != INF {
+                let bound = if ecc == INF { INF } else { ecc.saturating_sub(d_from) };
+                lo = std::cmp::max(lo, bound);
+            }
+            // ecc(v) <= d(v, w) + ecc(w).
+            let hi = if d_to == INF || ecc == INF { INF } else { d_to + ecc };
+            lower[vu] = lo;
+            upper[vu] = std::cmp::min(upper[vu], hi);
+        }
+
+        let diam_lower = lower.iter().cloned().max().unwrap_or(0);
+        let rad_upper = upper.iter().cloned().min().unwrap_or(INF);
+        // a vertex is settled once its eccentricity is known, or once it can
+        // no longer be in either the periphery or the center.
+        candidates.retain(|v| {
+            let vu = v.as_();
+            lower[vu] != upper[vu] && !(upper[vu] < diam_lower && lower[vu] > rad_upper)
+        });
+        if candidates.is_empty() {
+            break;
+        }
+
+        // alternate between the largest upper and the smallest lower bound.
+        w = if pick_upper {
+            candidates.iter().cloned().max_by_key(|v| (upper[v.as_()], degree(*v)))
+        } else {
+            candidates.iter().cloned().min_by_key(|v| (lower[v.as_()], std::cmp::Reverse(degree(*v))))
+        }.expect("Invalid candidates");
+        pick_upper = !pick_upper;
+    }
+
+    let diameter = lower.iter().cloned().max().unwrap_or(0);
+    let radius = upper.iter().cloned().min().unwrap_or(0);
+    let known = |v: &V, ecc: usize| lower[v.as_()] == upper[v.as_()] && lower[v.as_()] == ecc;
+    DistanceMeasures {
+        diameter: to_level(diameter),
+        radius: to_level(radius),
+        center: g.vertices().filter(|v| known(v, radius)).collect(),
+        periphery: g.vertices().filter(|v| known(v, diameter)).collect(),
+    }
+}
+
+pub fn diameter<V>(g: &impl Graph<V>) -> V
+where
+    V: PrimInt + AsPrimitive<usize>,
+    usize: AsPrimitive<V>,
+{
+    distance_measures(g).diameter
+}
+
+pub fn radius<V>(g: &impl Graph<V>) -> V
+where
+    V: PrimInt + AsPrimitive<usize>,
+    usize: AsPrimitive<V>,
+{
+    distance_measures(g).radius
+}
+
+/// Quick lower bound on the diameter from two breadth-first searches: the
+/// eccentricity of the vertex farthest from `src`. Returns the bound and
+/// that vertex.
+pub fn double_sweep<V>(g: &impl Graph<V>, src: V) -> (V, V)
+where
+    V: PrimInt + AsPrimitive<usize>,
+    usize: AsPrimitive<V>,
+{
+    let levels = bfs(g, src);
+    if levels.iter().any(|l| *l == V::max_value()) {
+        return (V::max_value(), src);
+    }
+    let (far, _) = levels
+        .iter()
+        .enumerate()
+        .max_by_key(|(_, l)| **l)
+        .expect("Invalid levels");
+    let far: V = far.as_();
+    let ecc = eccentricity_from_levels(&bfs(g, far));
+    (to_level(ecc), far)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{random_digraph, random_edges, random_graph};
+    use crate::{StaticDiGraph, StaticGraph, Vertex};
+
+    // distance measures from the eccentricity of every vertex.
+    fn check<G: Graph<Vertex>>(g: &G) {
+        let ecc: Vec<Vertex> = g.vertices().map(|v| bfs(g, v).into_iter().max().unwrap()).collect();
+        let diameter = *ecc.iter().max().unwrap();
+        let radius = *ecc.iter().min().unwrap();
+        let m = distance_measures(g);
+        assert_eq!((m.diameter, m.radius), (diameter, radius));
+        if radius != Vertex::MAX {
+            assert_eq!(m.center, g.vertices().filter(|v| ecc[*v as usize] == radius).collect::<Vec<_>>());
+        }
+        if diameter != Vertex::MAX {
+            assert_eq!(m.periphery, g.vertices().filter(|v| ecc[*v as usize] == diameter).collect::<Vec<_>>());
+            assert!(double_sweep(g, 0).0 <= diameter);
+        }
+    }
+
+    #[test]
+    fn small_graphs() {
+        let path = StaticGraph::from_edges(5, (1..5).map(|v| (v - 1, v)).collect());
+        let m = distance_measures(&path);
+        assert_eq!((m.diameter, m.radius, m.center, m.periphery), (4, 2, vec![2], vec![0, 4]));
+        assert_eq!(double_sweep(&path, 2).0, 4);
+        check(&path);
+
+        let cycle = StaticDiGraph::from_edges(6, (0..6).map(|v| (v, (v + 1) % 6)).collect());
+        assert_eq!((diameter(&cycle), radius(&cycle)), (5, 5));
+        check(&cycle);
+
+        let disconnected = StaticGraph::from_edges(4, vec![(0, 1), (2, 3)]);
+        assert_eq!((diameter(&disconnected), radius(&disconnected)), (Vertex::MAX, Vertex::MAX));
+        check(&disconnected);
+        check(&StaticGraph::from_edges(1, vec![]));
+        assert_eq!(diameter(&StaticGraph::from_edges(0, vec![])), 0);
+    }
+
+    #[test]
+    fn random_graphs() {
+        for seed in 0..10 {
+            // a ring lattice with a few chords: connected, with a large diameter.
+            let mut edges: Vec<(Vertex, Vertex)> = (0..200).flat_map(|v| vec![(v, (v + 1) % 200), (v, (v + 2) % 200)]).collect();
+            edges.extend(random_edges(200, 8, false, seed));
+            check(&StaticGraph::from_edges(200, edges));
+            check(&random_graph(100, 300, seed));
+            check(&random_graph(100, 80, seed));
+            check(&random_digraph(60, 400, seed));
+            check(&random_digraph(60, 100, seed));
+        }
+    }
+}
@@ -0,0 +1,502 @@
+use std::collections::{HashMap, VecDeque};
+use ordered_float::OrderedFloat;
+use priority_queue::PriorityQueue;
+
+use num::cast::AsPrimitive;
+use num::traits::PrimInt;
+use crate::traits::Graph;
+
+/// A maximum s-t flow and a minimum s-t cut.
+pub struct MaxFlow<V, W> {
+    pub value: W,
+    /// Flow on every edge, in CSR edge order (see `Graph::edge_offsets`).
+    pub flows: Vec<W>,
+    /// Vertices on the source side of a minimum cut, in ascending order: those
+    /// reachable from the source in the residual graph. The edges leaving
+    /// them are saturated and their capacities sum to `value`.
+    pub source_side: Vec<V>,
+}
+
+// residual graph with one arc per edge and one reverse arc. The arcs of a
+// vertex are its out-edges in CSR order, then the reverses of its in-edges.
+struct Residual<W> {
+    offsets: Vec<usize>,
+    heads: Vec<usize>,
+    twins: Vec<usize>,
+    caps: Vec<W>,
+    // capacity of every edge, in CSR order.
+    edge_caps: Vec<W>,
+    // arc of every edge, in CSR order.
+    edge_arcs: Vec<usize>,
+}
+
+impl<W: num::Float> Residual<W> {
+    fn new<V>(g: &impl Graph<V>, capacities: fn(V, V) -> W) -> Self
+    where
+        V: PrimInt + AsPrimitive<usize>,
+    {
+        let n = g.nv().as_();
+        let mut offsets = Vec::with_capacity(n + 1);
+        offsets.push(0);
+        for v in g.vertices() {
+            let count = g.out_degree(v).as_() + g.in_degree(v).as_();
+            offsets.push(offsets[offsets.len() - 1] + count);
+        }
+        let narcs = offsets[n];
+        let mut heads = vec![0usize; narcs];
+        let mut twins = vec![0usize; narcs];
+        let mut caps = vec![W::zero(); narcs];
+        let mut edge_caps = Vec::with_capacity(g.ne());
+        let mut edge_arcs = Vec::with_capacity(g.ne());
+        for u in g.vertices() {
+            let uu = u.as_();
+            for (i, v) in g.out_neighbors(u).iter().enumerate() {
+                let c = capacities(u, *v);
+                assert!(c >= W::zero(), "capacities must be non-negative");
+                let vu = v.as_();
+                let a = offsets[uu] + i;
+                let j = g.in_neighbors(*v).binary_search(&u).expect("Invalid edge");
+                let r = offsets[vu] + g.out_degree(*v).as_() + j;
+                heads[a] = vu;
+                heads[r] = uu;
+                twins[a] = r;
+                twins[r] = a;
+                caps[a] = c;
+                edge_caps.push(c);
+                edge_arcs.push(a);
+            }
+        }
+        Residual { offsets, heads, twins, caps, edge_caps, edge_arcs }
+    }
+
+    fn nv(&self) -> usize {
+        self.offsets.len() - 1
+    }
+
+    fn push(&mut self, a: usize, amount: W) {
+        self.caps[a] = self.caps[a] - amount;
+        let r = self.twins[a];
+        self.caps[r] = self.caps[r] + amount;
+    }
+
+    // BFS distances from `src` along residual arcs, or backwards along them
+    // (towards `src`) if `reverse`. Unreached vertices get usize::MAX.
+    fn distances(&self, src: usize, reverse: bool) -> Vec<usize> {
+        let mut dists = vec![usize::MAX; self.nv()];
+        let mut queue: VecDeque<usize> = VecDeque::new();
+        dists[src] = 0;
+        queue.push_back(src);
+        while let Some(u) = queue.pop_front() {
+            for a in self.offsets[u]..self.offsets[u + 1] {
+                let v = self.heads[a];
+                let cap = if reverse { self.caps[self.twins[a]] } else { self.caps[a] };
+                if cap > W::zero() && dists[v] == usize::MAX {
+                    dists[v] = dists[u] + 1;
+                    queue.push_back(v);
+                }
+            }
+        }
+        dists
+    }
+
+    fn result<V>(&self, s: usize, t: usize) -> MaxFlow<V, W>
+    where
+        V: PrimInt + 'static,
+        usize: AsPrimitive<V>,
+    {
+        let flows: Vec<W> = self.edge_arcs.iter().zip(self.edge_caps.iter())
+            .map(|(a, c)| *c - self.caps[*a])
+            .collect();
+        // net flow into the sink.
+        let mut value = W::zero();
+        for (a, f) in self.edge_arcs.iter().zip(flows.iter()) {
+            let (tail, head) = (self.heads[self.twins[*a]], self.heads[*a]);
+            if head == t && tail != t {
+                value = value + *f;
+            } else if tail == t && head != t {
+                value = value - *f;
+            }
+        }
+        let reached = self.distances(s, false);
+        MaxFlow {
+            value,
+            flows,
+            source_side: (0..self.nv()).filter(|v| reached[*v] != usize::MAX).map(|v| v.as_()).collect(),
+        }
+    }
+}
+
+fn check_terminals<V>(g: &impl Graph<V>, s: V, t: V)
+where
+    V: PrimInt + AsPrimitive<usize>,
+{
+    assert!(s < g.nv() && t < g.nv(), "source and sink must be vertices");
+    assert!(s != t, "source and sink must differ");
+}
+
+/// Dinic's maximum flow along the out-edges of `g`, with non-negative
+/// capacities. Each phase finds a blocking flow in the level graph of the
+/// shortest augmenting paths.
+pub fn dinic_max_flow<V, W>(g: &impl Graph<V>, s: V, t: V, capacities: fn(V, V) -> W) -> MaxFlow<V, W>
+where
+    V: PrimInt + AsPrimitive<usize>,
+    usize: AsPrimitive<V>,
+    W: num::Float,
+{
+    check_terminals(g, s, t);
+    let mut res = Residual::new(g, capacities);
+    let (su, tu) = (s.as_(), t.as_());
+    loop {
+        let mut levels = res.distances(su, false);
+        if levels[tu] == usize::MAX {
+            break;
+        }
+        // blocking flow by depth-first search along the level graph, keeping
+        // the path as a stack of arcs and the next arc to try at every vertex.
+        let mut next: Vec<usize> = res.offsets[..res.nv()].to_vec();
+        let mut path: Vec<usize> = Vec::new();
+        let mut u = su;
+        loop {
+            if u == tu {
+                let amount = path.iter().map(|a| res.caps[*a]).fold(W::infinity(), W::min);
+                for a in path.iter() {
+                    res.push(*a, amount);
+                }
+                // retreat to the tail of the first saturated arc.
+                let k = path.iter().position(|a| res.caps[*a] <= W::zero()).unwrap_or(0);
+                path.truncate(k);
+                u = path.last().map_or(su, |a| res.heads[*a]);
+                continue;
+            }
+            let end = res.offsets[u + 1];
+            while next[u] < end {
+                let a = next[u];
+                let v = res.heads[a];
+                if res.caps[a] > W::zero() && levels[v] == levels[u] + 1 {
+                    break;
+                }
+                next[u] += 1;
+            }
+            if next[u] < end {
+                let a = next[u];
+                path.push(a);
+                u = res.heads[a];
+            } else {
+                // dead end: remove u from the level graph.
+                levels[u] = usize::MAX;
+                match path.pop() {
+                    Some(a) => {
+                        u = res.heads[res.twins[a]];
+                        next[u] += 1;
+                    }
+                    None => break,
+                }
+            }
+        }
+    }
+    res.result(su, tu)
+}
+
+/// Push-relabel maximum flow (Goldberg and Tarjan, 1988) along the out-edges
+/// of `g`, with non-negative capacities. Active vertices are discharged in
+/// FIFO order, and all labels are recomputed exactly by a global relabeling
+/// after every n relabels.
+pub fn push_relabel_max_flow<V, W>(g: &impl Graph<V>, s: V, t: V, capacities: fn(V, V) -> W) -> MaxFlow<V, W>
+where
+    V: PrimInt + AsPrimitive<usize>,
+    usize: AsPrimitive<V>,
+    W: num::Float,
+{
+    check_terminals(g, s, t);
+    let mut res = Residual::new(g, capacities);
+    let n = res.nv();
+    let (su, tu) = (s.as_(), t.as_());
+    let mut excess = vec![W::zero(); n];
+    let mut heights = vec![0usize; n];
+    let mut queue: VecDeque<usize> = VecDeque::new();
+
+    // labels are distances to the sink, or n plus the distance to the source
+    // for vertices that can no longer reach the sink and must send their
+    // excess back.
+    let global_relabel = |res: &Residual<W>, heights: &mut [usize]| {
+        let to_sink = res.distances(tu, true);
+        let to_source = res.distances(su, true);
+        for v in 0..n {
+            heights[v] = if to_sink[v] != usize::MAX {
+                to_sink[v]
+            } else if to_source[v] != usize::MAX {
+                n + to_source[v]
+            } else {
+                2 * n
+            };
+        }
+        heights[su] = n;
+    };
+
+    for a in res.offsets[su]..res.offsets[su + 1] {
+        let (v, c) = (res.heads[a], res.caps[a]);
+        if c > W::zero() && v != su {
+            res.push(a, c);
+            if excess[v] == W::zero() && v != tu {
+                queue.push_back(v);
+            }
+            excess[v] = excess[v] + c;
+        }
+    }
+    global_relabel(&res, &mut heights);
+
+    let mut next: Vec<usize> = res.offsets[..n].to_vec();
+    let mut relabels = 0;
+    while let Some(u) = queue.pop_front() {
+        let (start, end) = (res.offsets[u], res.offsets[u + 1]);
+        while excess[u] > W::zero() {
+            if next[u] == end {
+                heights[u] = (start..end)
+                    .filter(|a| res.caps[*a] > W::zero())
+                    .map(|a| heights[res.heads[a]] + 1)
+                    .min()
+                    .expect("Invalid residual graph");
+                next[u] = start;
+                relabels += 1;
+                continue;
+            }
+            let a = next[u];
+            let v = res.heads[a];
+            if res.caps[a] > W::zero() && heights[u] == heights[v] + 1 {
+                let amount = excess[u].min(res.caps[a]);
+                res.push(a, amount);
+                excess[u] = excess[u] - amount;
+                if excess[v] == W::zero() && v != su && v != tu {
+                    queue.push_back(v);
+                }
+                excess[v] = excess[v] + amount;
+            } else {
+                next[u] += 1;
+            }
+        }
+        if relabels >= n {
+            global_relabel(&res, &mut heights);
+            next.copy_from_slice(&res.offsets[..n]);
+            relabels = 0;
+        }
+    }
+    res.result(su, tu)
+}
+
+/// A minimum cut of an undirected graph.
+pub struct GlobalMinCut<V, W> {
+    pub value: W,
+    /// Vertices on one side of the cut, in ascending order.
+    pub side: Vec<V>,
+}
+
+/// Stoer-Wagner global minimum cut of an undirected graph with non-negative,
+/// symmetric edge weights. Each phase orders the vertices by maximum
+/// adjacency and merges the last two.
+pub fn stoer_wagner_min_cut<V, W>(g: &impl Graph<V>, weights: fn(V, V) -> W) -> GlobalMinCut<V, W>
+where
+    V: PrimInt + AsPrimitive<usize>,
+    usize: AsPrimitive<V>,
+    W: num::Float,
+{
+    let n = g.nv().as_();
+    assert!(n >= 2, "a cut needs at least two vertices");
+    // adjacency of the merged vertices, and the original vertices of each.
+    let mut adj: Vec<HashMap<usize, W>> = vec![HashMap::new(); n];
+    for u in g.vertices() {
+        for v in g.out_neighbors(u).iter().filter(|v| **v != u) {
+            let w = weights(u, *v);
+            assert!(w >= W::zero(), "weights must be non-negative");
+            adj[u.as_()].insert(v.as_(), w);
+        }
+    }
+    let mut members: Vec<Vec<usize>> = (0..n).map(|v| vec![v]).collect();
+    let mut active: Vec<usize> = (0..n).collect();
+    let mut best = W::infinity();
+    let mut best_side: Vec<usize> = Vec::new();
+
+    while active.len() > 1 {
+        let mut pq = PriorityQueue::<usize, OrderedFloat<W>>::new();
+        for v in active.iter() {
+            pq.push(*v, OrderedFloat(W::zero()));
+        }
+        let (mut prev, mut last) = (usize::MAX, usize::MAX);
+        let mut cut = W::zero();
+        while let Some((u, OrderedFloat(w))) = pq.pop() {
+            prev = last;
+            last = u;
+            cut = w;
+            for (v, wv) in adj[u].iter() {
+                if let Some(OrderedFloat(p)) = pq.get_priority(v).cloned() {
+                    pq.change_priority(v, OrderedFloat(p + *wv));
+                }
+            }
+        }
+        if cut < best {
+            best = cut;
+            best_side = members[last].clone();
+        }
+
+        // merge `last` into `prev`.
+        let last_adj = std::mem::take(&mut adj[last]);
+        for (v, w) in last_adj {
+            adj[v].remove(&last);
+            if v != prev {
+                let e = adj[prev].entry(v).or_insert_with(W::zero);
+                *e = *e + w;
+                let e = adj[v].entry(prev).or_insert_with(W::zero);
+                *e = *e + w;
+            }
+        }
+        let moved = std::mem::take(&mut members[last]);
+        members[prev].extend(moved);
+        active.retain(|v| *v != last);
+    }
+    best_side.sort_unstable();
+    GlobalMinCut { value: best, side: best_side.into_iter().map(|v| v.as_()).collect() }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{random_digraph, random_graph};
+    use crate::{StaticDiGraph, StaticGraph, Vertex};
+
+    // the flow network of Cormen et al., with a maximum flow of 23.
+    fn clrs_capacity(u: Vertex, v: Vertex) -> f64 {
+        match (u, v) {
+            (0, 1) => 16.0,
+            (0, 2) => 13.0,
+            (2, 1) => 4.0,
+            (1, 3) => 12.0,
+            (3, 2) => 9.0,
+            (2, 4) => 14.0,
+            (4, 3) => 7.0,
+            (3, 5) => 20.0,
+            (4, 5) => 4.0,
+            _ => panic!("Invalid edge"),
+        }
+    }
+
+    fn capacity(u: Vertex, v: Vertex) -> f64 {
+        ((u * 7 + v * 13) % 10) as f64 + 0.5
+    }
+
+    // checks capacities, conservation and that the cut has the flow's capacity.
+    fn check_flow(g: &StaticDiGraph, s: Vertex, t: Vertex, f: &MaxFlow<Vertex, f64>, capacities: fn(Vertex, Vertex) -> f64) {
+        let offsets = g.edge_offsets();
+        let mut net = vec![0.0; g.nv() as usize];
+        let mut side = vec![false; g.nv() as usize];
+        f.source_side.iter().for_each(|v| side[*v as usize] = true);
+        assert!(side[s as usize] && !side[t as usize]);
+        let mut cut = 0.0;
+        for u in g.vertices() {
+            for (i, v) in g.out_neighbors(u).iter().enumerate() {
+                let flow = f.flows[offsets[u as usize] + i];
+                assert!(flow >= -1e-9 && flow <= capacities(u, *v) + 1e-9);
+                net[u as usize] -= flow;
+                net[*v as usize] += flow;
+                if side[u as usize] && !side[*v as usize] {
+                    cut += capacities(u, *v);
+                }
+            }
+        }
+        for v in g.vertices().filter(|v| *v != s && *v != t) {
+            assert!(net[v as usize].abs() < 1e-9);
+        }
+        assert!((net[t as usize] - f.value).abs() < 1e-9);
+        assert!((cut - f.value).abs() < 1e-9);
+    }
+
+    #[test]
+    fn max_flow_small_network() {
+        let edges = vec![(0, 1), (0, 2), (2, 1), (1, 3), (3, 2), (2, 4), (4, 3), (3, 5), (4, 5)];
+        let g = StaticDiGraph::from_edges(6, edges);
+        for f in [dinic_max_flow(&g, 0, 5, clrs_capacity), push_relabel_max_flow(&g, 0, 5, clrs_capacity)].iter() {
+            assert_eq!(f.value, 23.0);
+            check_flow(&g, 0, 5, f, clrs_capacity);
+        }
+        // nothing reaches the sink.
+        let f = dinic_max_flow(&g, 5, 0, clrs_capacity);
+        assert_eq!((f.value, f.source_side.clone()), (0.0, vec![5]));
+        assert_eq!(push_relabel_max_flow(&g, 5, 0, clrs_capacity).value, 0.0);
+    }
+
+    #[test]
+    fn dinic_agrees_with_push_relabel() {
+        for seed in 0..40 {
+            let n = 5 + (seed as Vertex % 30);
+            let g = random_digraph(n, 3 * n as usize, seed);
+            let (s, t) = (0, n - 1);
+            let a = dinic_max_flow(&g, s, t, capacity);
+            let b = push_relabel_max_flow(&g, s, t, capacity);
+            check_flow(&g, s, t, &a, capacity);
+            check_flow(&g, s, t, &b, capacity);
+            assert!((a.value - b.value).abs() < 1e-9);
+        }
+    }
+
+    // the example of Stoer and Wagner, whose minimum cut {2, 3, 6, 7} weighs 4.
+    fn stoer_wagner_weight(u: Vertex, v: Vertex) -> f64 {
+        match (u.min(v), u.max(v)) {
+            (0, 1) => 2.0,
+            (0, 4) => 3.0,
+            (1, 2) => 3.0,
+            (1, 4) => 2.0,
+            (1, 5) => 2.0,
+            (2, 3) => 4.0,
+            (2, 6) => 2.0,
+            (3, 6) => 2.0,
+            (3, 7) => 2.0,
+            (4, 5) => 3.0,
+            (5, 6) => 1.0,
+            (6, 7) => 3.0,
+            _ => panic!("Invalid edge"),
+        }
+    }
+
+    fn weight(u: Vertex, v: Vertex) -> f64 {
+        ((u + v) % 5 + 1) as f64
+    }
+
+    fn cut_weight(g: &StaticGraph, side: &[bool], weights: fn(Vertex, Vertex) -> f64) -> f64 {
+        g.vertices()
+            .flat_map(|u| g.out_neighbors(u).iter().map(move |v| (u, *v)))
+            .filter(|(u, v)| side[*u as usize] && !side[*v as usize])
+            .map(|(u, v)| weights(u, v))
+            .sum()
+    }
+
+    #[test]
+    fn stoer_wagner_known_cut() {
+        let edges = vec![(0, 1), (0, 4), (1, 2), (1, 4), (1, 5), (2, 3), (2, 6), (3, 6), (3, 7), (4, 5), (5, 6), (6, 7)];
+        let g = StaticGraph::from_edges(8, edges);
+        let cut = stoer_wagner_min_cut(&g, stoer_wagner_weight);
+        assert_eq!(cut.value, 4.0);
+        let side = if cut.side.contains(&2) { cut.side } else { (0..8).filter(|v| !cut.side.contains(v)).collect() };
+        assert_eq!(side, vec![2, 3, 6, 7]);
+
+        // disconnected graphs have an empty cut.
+        let g = StaticGraph::from_edges(4, vec![(0, 1), (2, 3)]);
+        assert_eq!(stoer_wagner_min_cut(&g, weight).value, 0.0);
+    }
+
+    #[test]
+    fn stoer_wagner_brute_force() {
+        for seed in 0..20 {
+            let n = 2 + (seed as Vertex % 9);
+            let g = random_graph(n, (seed as usize * 7) % (n as usize * (n as usize - 1) / 2 + 1), seed);
+            let best = (1..(1u32 << n) - 1)
+                .map(|mask| {
+                    let side: Vec<bool> = (0..n).map(|v| mask >> v & 1 == 1).collect();
+                    cut_weight(&g, &side, weight)
+                })
+                .fold(f64::INFINITY, f64::min);
+            let cut = stoer_wagner_min_cut(&g, weight);
+            assert_eq!(cut.value, best);
+            assert!(!cut.side.is_empty() && cut.side.len() < n as usize);
+            let side: Vec<bool> = (0..n).map(|v| cut.side.contains(&v)).collect();
+            assert_eq!(cut_weight(&g, &side, weight), best);
+        }
+    }
+}
@@ -0,0 +1,371 @@
+use std::collections::HashSet;
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::{Rng, SeedableRng};
+use rayon::prelude::*;
+
+use crate::parallel::mix;
+use crate::{StaticDiGraph, StaticGraph, Vertex};
+
+// positions of the successes in a sequence of `len` Bernoulli(p) trials,
+// jumping from one to the next with geometrically distributed gaps
+// (Batagelj and Brandes, 2005).
+fn bernoulli_positions(len: u64, p: f64, rng: &mut StdRng, mut f: impl FnMut(u64)) {
+    assert!((0.0..=1.0).contains(&p), "edge probability must be in [0, 1]");
+    if p == 0.0 {
+        return;
+    }
+    if p == 1.0 {
+        (0..len).for_each(f);
+        return;
+    }
+    let log_q = (1.0 - p).ln();
+    let mut pos: u64 = 0;
+    loop {
+        let r: f64 = rng.gen();
+        let skip = ((1.0 - r).ln() / log_q).floor();
+        if skip >= (len - pos) as f64 {
+            return;
+        }
+        pos += skip as u64;
+        f(pos);
+        pos += 1;
+        if pos >= len {
+            return;
+        }
+    }
+}
+
+// the pair (u, v) with u < v at position k in the sequence
+// (0, 1), (0, 2), (1, 2), (0, 3), ... of the lower triangle, by row v.
+fn triangle_pair(k: u64) -> (Vertex, Vertex) {
+    let mut v = ((1.0 + (1.0 + 8.0 * k as f64).sqrt()) / 2.0) as u64;
+    // correct rounding errors of the square root.
+    while v * (v - 1) / 2 > k {
+        v -= 1;
+    }
+    while (v + 1) * v / 2 <= k {
+        v += 1;
+    }
+    ((k - v * (v - 1) / 2) as Vertex, v as Vertex)
+}
+
+// the ordered pair (u, v) with u != v at position k, by row u.
+fn ordered_pair(n: u64, k: u64) -> (Vertex, Vertex) {
+    let u = k / (n - 1);
+    let j = k % (n - 1);
+    let v = if j < u { j } else { j + 1 };
+    (u as Vertex, v as Vertex)
+}
+
+fn undirected_pairs(n: Vertex) -> u64 {
+    let n = n as u64;
+    n * n.saturating_sub(1) / 2
+}
+
+fn directed_pairs(n: Vertex) -> u64 {
+    let n = n as u64;
+    n * n.saturating_sub(1)
+}
+
+/// Erdős-Rényi G(n, p) random graph without self-loops: every pair of
+/// vertices is joined with probability `p`. Runs in time proportional to the
+/// number of edges by skipping over the absent ones.
+pub fn gnp_random_graph(n: Vertex, p: f64, seed: u64) -> StaticGraph {
+    let mut rng = StdRng::seed_from_u64(seed);
+    let mut edgelist: Vec<(Vertex, Vertex)> = Vec::new();
+    bernoulli_positions(undirected_pairs(n), p, &mut rng, |k| edgelist.push(triangle_pair(k)));
+    StaticGraph::from_edges(n, edgelist)
+}
+
+/// Directed G(n, p): every ordered pair of distinct vertices is an edge with
+/// probability `p`.
+pub fn gnp_random_digraph(n: Vertex, p: f64, seed: u64) -> StaticDiGraph {
+    let mut rng = StdRng::seed_from_u64(seed);
+    let mut edgelist: Vec<(Vertex, Vertex)> = Vec::new();
+    bernoulli_positions(directed_pairs(n), p, &mut rng, |k| edgelist.push(ordered_pair(n as u64, k)));
+    StaticDiGraph::from_edges(n, edgelist)
+}
+
+// `m` distinct positions out of `len`, uniformly at random.
+fn sample_positions(len: u64, m: usize, rng: &mut StdRng) -> Vec<u64> {
+    assert!(m as u64 <= len, "too many edges for the number of vertices");
+    rand::seq::index::sample(rng, len as usize, m).into_iter().map(|k| k as u64).collect()
+}
+
+/// Erdős-Rényi G(n, m) random graph: `m` distinct edges chosen uniformly
+/// among all pairs of distinct vertices.
+pub fn gnm_random_graph(n: Vertex, m: usize, seed: u64) -> StaticGraph {
+    let mut rng = StdRng::seed_from_u64(seed);
+    let edgelist = sample_positions(undirected_pairs(n), m, &mut rng).into_iter().map(triangle_pair).collect();
+    StaticGraph::from_edges(n, edgelist)
+}
+
+/// Directed G(n, m): `m` distinct edges chosen uniformly among all ordered
+/// pairs of distinct vertices.
+pub fn gnm_random_digraph(n: Vertex, m: usize, seed: u64) -> StaticDiGraph {
+    let mut rng = StdRng::seed_from_u64(seed);
+    let edgelist = sample_positions(directed_pairs(n), m, &mut rng).into_iter()
+        .map(|k| ordered_pair(n as u64, k))
+        .collect();
+    StaticDiGraph::from_edges(n, edgelist)
+}
+
+/// Barabási-Albert preferential attachment graph. Starts from `k` isolated
+/// vertices; every new vertex is then joined to `k` distinct existing ones,
+/// each picked with probability proportional to its degree.
+pub fn barabasi_albert(n: Vertex, k: Vertex, seed: u64) -> StaticGraph {
+    assert!(k >= 1 && k < n, "k must be in [1, n)");
+    let mut rng = StdRng::seed_from_u64(seed);
+    let mut edgelist: Vec<(Vertex, Vertex)> = Vec::with_capacity(((n - k) * k) as usize);
+    // every vertex appears once per incident edge, so that uniform picks are
+    // proportional to the degree.
+    let mut repeated: Vec<Vertex> = Vec::with_capacity(2 * edgelist.capacity());
+    let mut targets: Vec<Vertex> = (0..k).collect();
+    let mut picked: HashSet<Vertex> = HashSet::new();
+    for src in k..n {
+        for t in targets.iter() {
+            edgelist.push((src, *t));
+            repeated.push(*t);
+            repeated.push(src);
+        }
+        picked.clear();
+        targets.clear();
+        while targets.len() < k as usize {
+            let t = repeated[rng.gen_range(0, repeated.len())];
+            if picked.insert(t) {
+                targets.push(t);
+            }
+        }
+    }
+    StaticGraph::from_edges(n, edgelist)
+}
+
+/// Watts-Strogatz small-world graph: a ring where every vertex is joined to
+/// its `k` nearest neighbors (`k / 2` on each side), whose edges (u, v) are
+/// then rewired with probability `beta` to (u, w) for a uniformly random w,
+/// avoiding self-loops and duplicate edges.
+pub fn watts_strogatz(n: Vertex, k: Vertex, beta: f64, seed: u64) -> StaticGraph {
+    assert!(k & 1 == 0 && k < n, "k must be even and smaller than n");
+    assert!((0.0..=1.0).contains(&beta), "rewiring probability must be in [0, 1]");
+    let mut rng = StdRng::seed_from_u64(seed);
+    let key = |u: Vertex, v: Vertex| if u < v { (u, v) } else { (v, u) };
+    let mut edges: HashSet<(Vertex, Vertex)> = HashSet::new();
+    for u in 0..n {
+        for j in 1..=k / 2 {
+            edges.insert(key(u, (u + j) % n));
+        }
+    }
+    let mut degrees = vec![k; n as usize];
+    for j in 1..=k / 2 {
+        for u in 0..n {
+            if !rng.gen_bool(beta) || degrees[u as usize] >= n - 1 {
+                continue;
+            }
+            let v = (u + j) % n;
+            let w = loop {
+                let w = rng.gen_range(0, n);
+                if w != u && !edges.contains(&key(u, w)) {
+                    break w;
+                }
+            };
+            edges.remove(&key(u, v));
+            edges.insert(key(u, w));
+            degrees[v as usize] -= 1;
+            degrees[w as usize] += 1;
+        }
+    }
+    StaticGraph::from_edges(n, edges.into_iter().collect())
+}
+
+#[derive(Clone, Debug)]
+pub struct RmatConfig {
+    /// The graph has 2^scale vertices.
+    pub scale: u32,
+    /// Number of generated edges per vertex.
+    pub edge_factor: usize,
+    /// Probabilities of recursing into the top-left, top-right, bottom-left
+    /// and bottom-right quadrant of the adjacency matrix. They are normalized
+    /// to sum to one.
+    pub a: f64,
+    pub b: f64,
+    pub c: f64,
+    pub d: f64,
+    /// Relabel the vertices by a random permutation, so that ids do not
+    /// reveal degrees.
+    pub permute: bool,
+    pub seed: u64,
+}
+
+impl Default for RmatConfig {
+    /// The Graph500 parameters at scale 16.
+    fn default() -> Self {
+        RmatConfig {
+            scale: 16,
+            edge_factor: 16,
+            a: 0.57,
+            b: 0.19,
+            c: 0.19,
+            d: 0.05,
+            permute: true,
+            seed: 0,
+        }
+    }
+}
+
+// edges generated from the same random stream, so that the output does not
+// depend on the number of threads.
+const RMAT_CHUNK: usize = 1 << 16;
+
+/// The edge list of an R-MAT (recursive matrix) graph, a Kronecker graph with
+/// a 2x2 initiator (Chakrabarti, Zhan and Faloutsos, 2004): each of the
+/// `edge_factor * 2^scale` edges picks one quadrant of the adjacency matrix
+/// per bit of the vertex ids. Edges are generated in parallel and may include
+/// self-loops and duplicates, as in the Graph500 benchmark.
+pub fn rmat_edges(config: &RmatConfig) -> Vec<(Vertex, Vertex)> {
+    assert!(config.scale < 32, "scale must be below 32");
+    let probs = [config.a, config.b, config.c, config.d];
+    assert!(probs.iter().all(|p| *p >= 0.0), "quadrant probabilities must be non-negative");
+    let total: f64 = probs.iter().sum();
+    assert!(total > 0.0, "quadrant probabilities must not be all zeros");
+    let (ab, abc) = ((probs[0] + probs[1]) / total, (probs[0] + probs[1] + probs[2]) / total);
+    let a = probs[0] / total;
+
+    let n = 1usize << config.scale;
+    let m = config.edge_factor * n;
+    let nchunks = m.div_ceil(RMAT_CHUNK);
+    let mut edgelist: Vec<(Vertex, Vertex)> = (0..nchunks).into_par_iter().flat_map(|chunk| {
+        let mut rng = StdRng::seed_from_u64(mix(config.seed ^ mix(chunk as u64)));
+        let len = RMAT_CHUNK.min(m - chunk * RMAT_CHUNK);
+        (0..len).map(|_| {
+            let (mut u, mut v): (Vertex, Vertex) = (0, 0);
+            for bit in (0..config.scale).rev() {
+                let r: f64 = rng.gen();
+                if r >= abc {
+                    u |= 1 << bit;
+                    v |= 1 << bit;
+                } else if r >= ab {
+                    u |= 1 << bit;
+                } else if r >= a {
+                    v |= 1 << bit;
+                }
+            }
+            (u, v)
+        }).collect::<Vec<_>>()
+    }).collect();
+
+    if config.permute {
+        let mut perm: Vec<Vertex> = (0..n).map(|v| v as Vertex).collect();
+        perm.shuffle(&mut StdRng::seed_from_u64(mix(config.seed)));
+        edgelist.par_iter_mut().for_each(|(u, v)| {
+            *u = perm[*u as usize];
+            *v = perm[*v as usize];
+        });
+    }
+    edgelist
+}
+
+/// A directed R-MAT graph (see `rmat_edges`), without duplicate edges.
+pub fn rmat_digraph(config: &RmatConfig) -> StaticDiGraph {
+    StaticDiGraph::from_edges(1 << config.scale, rmat_edges(config))
+}
+
+/// An undirected R-MAT graph (see `rmat_edges`), without duplicate edges, as
+/// used by the Graph500 breadth-first search benchmark.
+pub fn rmat_graph(config: &RmatConfig) -> StaticGraph {
+    StaticGraph::from_edges(1 << config.scale, rmat_edges(config))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parallel::with_threads;
+    use crate::traits::Graph;
+
+    fn edges<G: Graph<Vertex>>(g: &G) -> Vec<(Vertex, Vertex)> {
+        g.vertices().flat_map(|u| g.out_neighbors(u).iter().map(move |v| (u, *v))).collect()
+    }
+
+    fn no_self_loops<G: Graph<Vertex>>(g: &G) -> bool {
+        edges(g).iter().all(|(u, v)| u != v)
+    }
+
+    #[test]
+    fn erdos_renyi() {
+        for seed in 0..5 {
+            let g = gnm_random_graph(100, 300, seed);
+            assert_eq!((g.nv(), g.ne()), (100, 600));
+            assert!(no_self_loops(&g));
+            assert_eq!(edges(&g), edges(&gnm_random_graph(100, 300, seed)));
+            assert_ne!(edges(&g), edges(&gnm_random_graph(100, 300, seed + 1)));
+
+            let d = gnm_random_digraph(50, 400, seed);
+            assert_eq!((d.nv(), d.ne()), (50, 400));
+            assert!(no_self_loops(&d));
+            assert_eq!(edges(&d), edges(&gnm_random_digraph(50, 400, seed)));
+
+            let g = gnp_random_graph(200, 0.05, seed);
+            assert!(no_self_loops(&g));
+            assert_eq!(edges(&g), edges(&gnp_random_graph(200, 0.05, seed)));
+            let d = gnp_random_digraph(200, 0.05, seed);
+            assert!(no_self_loops(&d));
+            assert_eq!(edges(&d), edges(&gnp_random_digraph(200, 0.05, seed)));
+        }
+        // every pair is an edge at p = 1, none at p = 0.
+        assert_eq!(gnp_random_graph(30, 1.0, 0).ne(), 30 * 29);
+        assert_eq!(gnp_random_digraph(30, 1.0, 0).ne(), 30 * 29);
+        assert_eq!(gnp_random_graph(30, 0.0, 0).ne(), 0);
+        assert_eq!(gnm_random_graph(30, 30 * 29 / 2, 0).ne(), 30 * 29);
+        // the number of edges is close to its expectation.
+        let ne = gnp_random_digraph(1000, 0.01, 3).ne() as f64;
+        assert!((ne - 9990.0).abs() < 500.0);
+    }
+
+    #[test]
+    fn preferential_attachment_and_small_world() {
+        for seed in 0..5 {
+            let g = barabasi_albert(500, 3, seed);
+            assert_eq!((g.nv(), g.ne()), (500, 2 * 497 * 3));
+            assert!(no_self_loops(&g));
+            assert_eq!(edges(&g), edges(&barabasi_albert(500, 3, seed)));
+
+            let g = watts_strogatz(500, 6, 0.2, seed);
+            assert_eq!((g.nv(), g.ne()), (500, 500 * 6));
+            assert!(no_self_loops(&g));
+            assert_eq!(edges(&g), edges(&watts_strogatz(500, 6, 0.2, seed)));
+        }
+        // without rewiring, the ring lattice.
+        let g = watts_strogatz(10, 4, 0.0, 1);
+        assert!(g.vertices().all(|u| g.out_neighbors(u).len() == 4 && g.has_edge(u, (u + 2) % 10)));
+    }
+
+    #[test]
+    fn rmat_is_reproducible() {
+        // several chunks, so that threads generate them concurrently.
+        let config = RmatConfig { scale: 14, edge_factor: 10, seed: 5, ..Default::default() };
+        let expected = rmat_edges(&config);
+        assert_eq!(expected.len(), 10 << 14);
+        assert!(expected.iter().all(|(u, v)| *u < 1 << 14 && *v < 1 << 14));
+        for nthreads in 1..5 {
+            assert_eq!(with_threads(Some(nthreads), || rmat_edges(&config)).unwrap(), expected);
+        }
+        assert_ne!(rmat_edges(&RmatConfig { seed: 6, ..config.clone() }), expected);
+
+        let g = rmat_graph(&config);
+        assert_eq!(g.nv(), 1 << 14);
+        assert_eq!(edges(&g), edges(&with_threads(Some(3), || rmat_graph(&config)).unwrap()));
+        let d = rmat_digraph(&config);
+        assert!(d.ne() <= expected.len());
+    }
+
+    #[test]
+    fn rmat_quadrants() {
+        // all edges fall in the top-left quadrant, so they join vertex 0 to itself.
+        let config = RmatConfig { scale: 5, a: 1.0, b: 0.0, c: 0.0, d: 0.0, permute: false, ..Default::default() };
+        assert!(rmat_edges(&config).iter().all(|e| *e == (0, 0)));
+        // without permutation, skewed parameters favor low ids.
+        let config = RmatConfig { scale: 10, permute: false, ..Default::default() };
+        let low = rmat_edges(&config).iter().filter(|(u, _)| *u < 512).count();
+        assert!(low as f64 > 0.7 * (16 << 10) as f64);
+    }
+}
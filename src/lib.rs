@@ -2,9 +2,26 @@ use std::error::Error;
 use crate::traits::Graph;
 use std::fmt;
 use std::io::BufRead;
+pub mod bipartite;
+pub mod centrality;
+pub mod cliques;
+pub mod coloring;
+pub mod community;
+pub mod components;
+pub mod cores;
+pub mod disjoint_set;
+pub mod distance;
+pub mod flow;
+pub mod generators;
+pub mod matching;
+pub mod parallel;
+pub mod spanning;
+#[cfg(test)]
+mod testing;
 pub mod traits;
 pub mod traversals;
 pub mod triangles;
+pub mod truss;
 
 pub type Vertex = u32;
 pub struct StaticGraph {
@@ -16,6 +33,50 @@ pub struct StaticDiGraph {
     badj: graph_matrix::GraphMatrix<Vertex>,
 }
 
+// builds a CSR matrix with exactly `nv` rows, so that trailing isolated
+// vertices are kept. Duplicate edges are removed.
+fn csr_from_edges(nv: Vertex, mut edgelist: Vec<(Vertex, Vertex)>) -> graph_matrix::GraphMatrix<Vertex> {
+    if let Some((src, dst)) = edgelist.iter().find(|(src, dst)| *src >= nv || *dst >= nv) {
+        panic!("edge ({}, {}) has an endpoint outside of 0..{}", src, dst, nv);
+    }
+    edgelist.sort_unstable();
+    edgelist.dedup();
+    let n = nv as usize;
+    let mut indptr: Vec<usize> = vec![0; n + 1];
+    for (src, _) in edgelist.iter() {
+        indptr[*src as usize + 1] += 1;
+    }
+    for i in 0..n {
+        indptr[i + 1] += indptr[i];
+    }
+    let indices: Vec<Vertex> = edgelist.into_iter().map(|(_, dst)| dst).collect();
+    graph_matrix::GraphMatrix::new(indptr, indices)
+}
+
+impl StaticGraph {
+    /// Creates a graph with `nv` vertices from a list of undirected edges.
+    /// Panics if an endpoint is not below `nv`.
+    pub fn from_edges(nv: Vertex, edgelist: Vec<(Vertex, Vertex)>) -> Self {
+        let mut edges: Vec<(Vertex, Vertex)> = Vec::with_capacity(edgelist.len() * 2);
+        for (src, dst) in edgelist {
+            edges.push((src, dst));
+            edges.push((dst, src));
+        }
+        StaticGraph { adj: csr_from_edges(nv, edges) }
+    }
+}
+
+impl StaticDiGraph {
+    /// Creates a directed graph with `nv` vertices from a list of edges.
+    /// Panics if an endpoint is not below `nv`.
+    pub fn from_edges(nv: Vertex, edgelist: Vec<(Vertex, Vertex)>) -> Self {
+        let bedges = edgelist.iter().map(|x| (x.1, x.0)).collect();
+        let fadj = csr_from_edges(nv, edgelist);
+        let badj = csr_from_edges(nv, bedges);
+        StaticDiGraph { fadj, badj }
+    }
+}
+
 impl Graph<Vertex> for StaticGraph {
     type VIterator = std::ops::Range<Vertex>;
     fn nv(&self) -> Vertex {
@@ -1,5 +1,10 @@
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use rustgraphs::distance::{distance_measures, double_sweep};
+use rustgraphs::generators::{rmat_graph, RmatConfig};
+use rustgraphs::parallel::with_threads;
 use rustgraphs::traits::Graph;
-use rustgraphs::{StaticDiGraph, StaticGraph, triangles::triangles, triangles::threaded_triangles, triangles::threaded_triangles_csr, traversals::bfs, traversals::dijkstra};
+use rustgraphs::{StaticDiGraph, StaticGraph, triangles::triangles, triangles::threaded_triangles, triangles::threaded_triangles_csr, triangles::threaded_triangles_with, triangles::Intersection, traversals::bfs, traversals::dijkstra};
 use std::env;
 use std::error::Error;
 use std::path::Path;
@@ -14,7 +19,15 @@ fn main() -> Result<(), Box<dyn Error>> {
     let src: u32 = src.parse().expect("invalid source");
 
     let op = &args[3];
+    let nthreads: Option<usize> = args.get(4).map(|s| s.parse().expect("invalid thread count"));
+    with_threads(nthreads, || {
+        println!("using {} threads", rayon::current_num_threads());
+        run(filename, src, op).map_err(|e| e.to_string())
+    })??;
+    Ok(())
+}
 
+fn run(filename: &str, src: u32, op: &str) -> Result<(), Box<dyn Error>> {
     let mut avg: f64 = 0.0;
 
     if op == "bfs" {
@@ -141,6 +154,84 @@ fn main() -> Result<(), Box<dyn Error>> {
             avg / NRUNS as f64
         );
     }
+    if op == "intersections" {
+        let now = Instant::now();
+        let h: StaticGraph = StaticGraph::from_edge_file(Path::new(filename))?;
+        println!("Load took {}ms", now.elapsed().as_micros() as f64 / 1000.0);
+        println!("h = {}", h);
+        for method in Intersection::ALL.iter() {
+            println!("starting first {:?} triangle count", method);
+            let ntri = threaded_triangles_with(&h, *method);
+            println!("{} triangles", ntri);
+            println!("starting looped {:?} triangle count", method);
+            let mut avg: f64 = 0.0;
+            for _ in 0..NRUNS {
+                let now = Instant::now();
+                let _ntri = threaded_triangles_with(&h, *method);
+                let elp = now.elapsed().as_micros() as f64 / 1000.0;
+                avg += elp;
+                print!(".");
+            }
+            println!();
+            println!(
+                "{:?} triangle count: average over {} runs: {:.3}ms",
+                method,
+                NRUNS,
+                avg / NRUNS as f64
+            );
+        }
+    }
+    if op == "diameter" {
+        let now = Instant::now();
+        let h: StaticDiGraph = StaticDiGraph::from_edge_file(Path::new(filename))?;
+        println!("Load took {}ms", now.elapsed().as_micros() as f64 / 1000.0);
+        println!("h = {}", h);
+
+        let now = Instant::now();
+        let (lower, far) = double_sweep(&h, src);
+        println!("double sweep took {}ms", now.elapsed().as_micros() as f64 / 1000.0);
+        println!("diameter >= {} (eccentricity of {})", lower, far);
+
+        let now = Instant::now();
+        let dm = distance_measures(&h);
+        println!("bounding took {}ms", now.elapsed().as_micros() as f64 / 1000.0);
+        println!("diameter = {}, radius = {}", dm.diameter, dm.radius);
+        println!("{} center vertices, {} periphery vertices", dm.center.len(), dm.periphery.len());
+    }
+    // Graph500-style benchmark: bfs from 64 random non-isolated sources of a
+    // generated R-MAT graph, whose scale is given instead of a file name.
+    if op == "graph500" {
+        let scale: u32 = filename.parse().expect("invalid scale");
+        let now = Instant::now();
+        let h: StaticGraph = rmat_graph(&RmatConfig { scale, seed: src as u64, ..Default::default() });
+        println!("Generation took {}ms", now.elapsed().as_micros() as f64 / 1000.0);
+        println!("h = {}", h);
+
+        let mut rng = StdRng::seed_from_u64(src as u64);
+        let mut inv_teps = 0.0;
+        let mut nsearches = 0;
+        // gives up if almost all vertices are isolated.
+        let mut attempts = 0;
+        while nsearches < 64 && attempts < 64 * 100 {
+            attempts += 1;
+            let s = rng.gen_range(0, h.nv());
+            if h.out_degree(s) == 0 {
+                continue;
+            }
+            let now = Instant::now();
+            let levels = bfs(&h, s);
+            let secs = now.elapsed().as_secs_f64();
+            let nedges: usize = h.vertices()
+                .filter(|v| levels[*v as usize] != u32::MAX)
+                .map(|v| h.out_degree(v) as usize)
+                .sum::<usize>() / 2;
+            inv_teps += secs / nedges as f64;
+            nsearches += 1;
+            print!(".");
+        }
+        println!();
+        println!("bfs harmonic mean over {} searches: {:.3e} TEPS", nsearches, nsearches as f64 / inv_teps);
+    }
     return Ok(())
 }
 
@@ -0,0 +1,272 @@
+use std::collections::VecDeque;
+use rayon::prelude::*;
+
+use num::cast::AsPrimitive;
+use num::traits::PrimInt;
+use crate::parallel::mix;
+use crate::traits::Graph;
+
+/// A matching: a set of edges without common endpoints.
+pub struct Matching<V> {
+    /// The vertex matched to every vertex, or `V::max_value()` if unmatched.
+    pub mates: Vec<V>,
+    /// Number of matched edges.
+    pub size: usize,
+}
+
+impl<V> Matching<V>
+where
+    V: PrimInt + AsPrimitive<usize>,
+{
+    /// Matched edges as (u, v) with u < v, in ascending order.
+    pub fn edges(&self) -> Vec<(V, V)> {
+        self.mates.iter().enumerate()
+            .filter(|(u, v)| **v != V::max_value() && *u < v.as_())
+            .map(|(_, v)| (self.mates[v.as_()], *v))
+            .collect()
+    }
+}
+
+/// Hopcroft-Karp maximum cardinality matching of a bipartite graph, whose
+/// sides are given by `sides` (see `bipartite::bipartition`). Each phase
+/// finds a maximal set of shortest vertex-disjoint augmenting paths, for
+/// O(m sqrt(n)) time overall.
+pub fn hopcroft_karp<V>(g: &impl Graph<V>, sides: &[bool]) -> Matching<V>
+where
+    V: PrimInt + AsPrimitive<usize>,
+    usize: AsPrimitive<V>,
+{
+    let n = g.nv().as_();
+    assert_eq!(sides.len(), n, "sides must have one entry per vertex");
+    let none = V::max_value();
+    let left: Vec<V> = g.vertices().filter(|v| !sides[v.as_()]).collect();
+    for u in left.iter() {
+        assert!(g.out_neighbors(*u).iter().all(|v| sides[v.as_()]), "every edge must join both sides");
+    }
+    let mut mates = vec![none; n];
+    let mut size = 0;
+    // BFS levels of the left vertices in the alternating forest grown from
+    // the free ones.
+    let mut dists = vec![usize::MAX; n];
+    let mut next = vec![0usize; n];
+    let mut queue: VecDeque<V> = VecDeque::new();
+    let mut stack: Vec<V> = Vec::new();
+    loop {
+        for u in left.iter() {
+            let uu = u.as_();
+            dists[uu] = if mates[uu] == none { 0 } else { usize::MAX };
+            if mates[uu] == none {
+                queue.push_back(*u);
+            }
+        }
+        // level of the left vertices ending the shortest augmenting paths:
+        // the first level with a free right neighbor. Later levels are not
+        // explored.
+        let mut limit = usize::MAX;
+        while let Some(u) = queue.pop_front() {
+            let du = dists[u.as_()];
+            if du >= limit {
+                queue.clear();
+                break;
+            }
+            for v in g.out_neighbors(u) {
+                let w = mates[v.as_()];
+                if w == none {
+                    limit = du;
+                } else if du < limit && dists[w.as_()] == usize::MAX {
+                    dists[w.as_()] = du + 1;
+                    queue.push_back(w);
+                }
+            }
+        }
+        if limit == usize::MAX {
+            break;
+        }
+
+        // augment along vertex-disjoint shortest paths by iterative DFS; the
+        // path is the stack of left vertices, each using its next edge. Free
+        // right vertices are only accepted at the last level.
+        for u in left.iter() {
+            next[u.as_()] = 0;
+        }
+        for root in left.iter() {
+            if mates[root.as_()] != none {
+                continue;
+            }
+            stack.push(*root);
+            while let Some(u) = stack.last().cloned() {
+                let uu = u.as_();
+                let nbrs = g.out_neighbors(u);
+                if next[uu] == nbrs.len() {
+                    // dead end: no shortest augmenting path through u.
+                    dists[uu] = usize::MAX;
+                    stack.pop();
+                    if let Some(p) = stack.last() {
+                        next[p.as_()] += 1;
+                    }
+                    continue;
+                }
+                let w = mates[nbrs[next[uu]].as_()];
+                if w == none && dists[uu] == limit {
+                    // the vertices of the path are not reused in this phase.
+                    for x in stack.drain(..) {
+                        let y = g.out_neighbors(x)[next[x.as_()]];
+                        mates[x.as_()] = y;
+                        mates[y.as_()] = x;
+                        dists[x.as_()] = usize::MAX;
+                    }
+                    size += 1;
+                } else if w != none && dists[uu] < limit && dists[w.as_()] == dists[uu] + 1 {
+                    stack.push(w);
+                } else {
+                    next[uu] += 1;
+                }
+            }
+        }
+    }
+    Matching { mates, size }
+}
+
+/// Greedy maximal matching of an undirected graph, as if adding the edges in
+/// a pseudo-random order drawn from the seed, each if both its endpoints are
+/// still free. Computed in parallel rounds: every free vertex proposes its
+/// first edge to a free neighbor, and mutual proposals are matched.
+pub fn maximal_matching<V, G>(g: &G, seed: u64) -> Matching<V>
+where
+    G: Graph<V> + Sync,
+    V: PrimInt + AsPrimitive<usize> + Send + Sync,
+    usize: AsPrimitive<V>,
+{
+    let n = g.nv().as_();
+    let none = V::max_value();
+    let salt = mix(seed);
+    let priority = |u: V, v: V| {
+        let (a, b) = if u < v { (u, v) } else { (v, u) };
+        (mix(salt ^ mix(a.as_() as u64) ^ (b.as_() as u64)), a, b)
+    };
+    let mut mates = vec![none; n];
+    let mut proposals = vec![none; n];
+    let mut size = 0;
+    let mut active: Vec<V> = g.vertices().collect();
+    while !active.is_empty() {
+        let mates_ref = &mates;
+        let proposed: Vec<(V, V)> = active.par_iter()
+            .filter_map(|u| {
+                g.out_neighbors(*u).iter()
+                    .filter(|v| **v != *u && mates_ref[v.as_()] == none)
+                    .min_by_key(|v| priority(*u, **v))
+                    .map(|v| (*u, *v))
+            })
+            .collect();
+        for (u, v) in proposed.iter() {
+            proposals[u.as_()] = *v;
+        }
+        for (u, v) in proposed.iter() {
+            if *u < *v && proposals[v.as_()] == *u {
+                mates[u.as_()] = *v;
+                mates[v.as_()] = *u;
+                size += 1;
+            }
+        }
+        for (u, _) in proposed.iter() {
+            proposals[u.as_()] = none;
+        }
+        active = proposed.into_iter().map(|(u, _)| u).filter(|u| mates[u.as_()] == none).collect();
+    }
+    Matching { mates, size }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bipartite::{bipartition, Bipartition};
+    use crate::testing::random_graph;
+    use crate::parallel::with_threads;
+    use crate::{StaticGraph, Vertex};
+    use rand::rngs::StdRng;
+    use rand::{Rng, SeedableRng};
+
+    fn check_matching(g: &StaticGraph, m: &Matching<Vertex>) {
+        let none = Vertex::MAX;
+        for (u, v) in m.mates.iter().enumerate().filter(|(_, v)| **v != none) {
+            assert_eq!(m.mates[*v as usize], u as Vertex);
+            assert!(g.has_edge(u as Vertex, *v) && u as Vertex != *v);
+        }
+        assert_eq!(m.edges().len(), m.size);
+    }
+
+    // maximum matching size by augmenting from every left vertex in turn.
+    fn augmenting_path_matching(g: &StaticGraph, sides: &[bool]) -> usize {
+        fn augment(g: &StaticGraph, u: Vertex, mates: &mut [Vertex], seen: &mut [bool]) -> bool {
+            for v in g.out_neighbors(u) {
+                if seen[*v as usize] {
+                    continue;
+                }
+                seen[*v as usize] = true;
+                let w = mates[*v as usize];
+                if w == Vertex::MAX || augment(g, w, mates, seen) {
+                    mates[*v as usize] = u;
+                    return true;
+                }
+            }
+            false
+        }
+        let mut mates = vec![Vertex::MAX; g.nv() as usize];
+        g.vertices()
+            .filter(|u| !sides[*u as usize])
+            .filter(|u| augment(g, *u, &mut mates, &mut vec![false; g.nv() as usize]))
+            .count()
+    }
+
+    // a random bipartite graph between 0..a and a..a + b.
+    fn random_bipartite(a: Vertex, b: Vertex, m: usize, seed: u64) -> StaticGraph {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let edges = (0..m).map(|_| (rng.gen_range(0, a), a + rng.gen_range(0, b))).collect();
+        StaticGraph::from_edges(a + b, edges)
+    }
+
+    #[test]
+    fn hopcroft_karp_is_maximum() {
+        // a path of 6 vertices and a star.
+        let path = StaticGraph::from_edges(6, (1..6).map(|v| (v - 1, v)).collect());
+        let sides: Vec<bool> = (0..6).map(|v| v % 2 == 1).collect();
+        let m = hopcroft_karp(&path, &sides);
+        assert_eq!((m.size, m.edges()), (3, vec![(0, 1), (2, 3), (4, 5)]));
+        let star = StaticGraph::from_edges(5, (1..5).map(|v| (0, v)).collect());
+        assert_eq!(hopcroft_karp(&star, &[true, false, false, false, false]).size, 1);
+
+        for seed in 0..50 {
+            let g = random_bipartite(20, 15 + seed as Vertex % 10, 10 + seed as usize * 2, seed);
+            let sides = match bipartition(&g) {
+                Bipartition::Coloring(sides) => sides,
+                Bipartition::OddCycle(_) => panic!("Invalid bipartite graph"),
+            };
+            let m = hopcroft_karp(&g, &sides);
+            check_matching(&g, &m);
+            assert_eq!(m.size, augmenting_path_matching(&g, &sides));
+        }
+        let g = random_bipartite(2000, 2000, 5000, 7);
+        let sides: Vec<bool> = g.vertices().map(|v| v >= 2000).collect();
+        let m = hopcroft_karp(&g, &sides);
+        check_matching(&g, &m);
+        assert_eq!(m.size, augmenting_path_matching(&g, &sides));
+    }
+
+    #[test]
+    fn maximal_matching_is_maximal() {
+        // a path with self-loops at both ends, then random graphs.
+        let mut graphs = vec![StaticGraph::from_edges(5, vec![(0, 0), (0, 1), (1, 2), (2, 3), (3, 3)])];
+        graphs.extend((0..10).map(|seed| random_graph(500, 800 + 100 * seed as usize, seed)));
+        for (seed, g) in graphs.iter().enumerate() {
+            let seed = seed as u64;
+            let m = maximal_matching(g, seed);
+            check_matching(g, &m);
+            // no edge has both endpoints free.
+            let free = |v: Vertex| m.mates[v as usize] == Vertex::MAX;
+            assert!(g.vertices().filter(|u| free(*u)).all(|u| g.out_neighbors(u).iter().all(|v| *v == u || !free(*v))));
+            for nthreads in 1..5 {
+                assert_eq!(with_threads(Some(nthreads), || maximal_matching(g, seed)).unwrap().mates, m.mates);
+            }
+        }
+    }
+}
@@ -0,0 +1,172 @@
+//! Helpers shared by the parallel algorithms.
+//!
+//! Parallel algorithms run on the current rayon thread pool. To pick the
+//! number of threads, call them through `with_threads`, for instance
+//! `with_threads(Some(4), || threaded_triangles(&g))?`, or from
+//! `ThreadPool::install` to reuse a pool; the number of work partitions
+//! follows the pool's thread count.
+use std::cmp;
+use std::ops::Range;
+
+/// Number of partitions created per thread, so that threads finishing early
+/// can pick up more work.
+pub const OVERSUBSCRIPTION: usize = 4;
+
+/// Builds a thread pool with `nthreads` threads, or one per logical CPU.
+pub fn thread_pool(nthreads: Option<usize>) -> Result<rayon::ThreadPool, rayon::ThreadPoolBuildError> {
+    rayon::ThreadPoolBuilder::new()
+        .num_threads(nthreads.unwrap_or_else(num_cpus::get))
+        .build()
+}
+
+/// Runs `op` on a new thread pool with `nthreads` threads, or one per logical
+/// CPU, and returns its result. Parallel algorithms called from `op` use that
+/// pool.
+pub fn with_threads<R, F>(nthreads: Option<usize>, op: F) -> Result<R, rayon::ThreadPoolBuildError>
+where
+    F: FnOnce() -> R + Send,
+    R: Send,
+{
+    Ok(thread_pool(nthreads)?.install(op))
+}
+
+/// Number of partitions to split work into on the current thread pool.
+pub fn n_partitions() -> usize {
+    rayon::current_num_threads() * OVERSUBSCRIPTION
+}
+
+// SplitMix64 finalizer, used to derive per-vertex pseudo-random values that
+// do not depend on which thread computes them.
+pub(crate) fn mix(mut x: u64) -> u64 {
+    x = (x ^ (x >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    x = (x ^ (x >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    x ^ (x >> 31)
+}
+
+/// Splits `0..weights.len()` into at most `n_partitions` contiguous, non-empty
+/// ranges, minimizing the largest sum of weights in a range.
+pub fn optimal_contiguous_partition(weights: &[usize], n_partitions: usize) -> Vec<Range<usize>>
+{
+    assert!(n_partitions > 0, "at least one partition is required");
+    let n_items = weights.len();
+    let total: usize = weights.iter().sum();
+    if total == 0 {
+        let whole = 0..n_items;
+        return vec![whole];
+    }
+
+    // a balance of up_bound is always achievable; low_bound never is.
+    let mut up_bound = total;
+    let max_weight = weights.iter().cloned().max().unwrap_or(0);
+    let mut low_bound = cmp::max(max_weight, total.div_ceil(n_partitions)) - 1;
+
+    while up_bound > low_bound + 1 {
+        let search_for = (up_bound + low_bound) / 2;
+        let mut sum_part = 0usize;
+        let mut remain_part = n_partitions;
+        let mut possible = true;
+
+        for w in weights.iter() {
+            sum_part += w;
+            if sum_part > search_for {
+                sum_part = *w;
+                remain_part -= 1;
+                if remain_part == 0 {
+                    possible = false;
+                    break;
+                }
+            }
+        }
+        if possible {
+            up_bound = search_for;
+        } else {
+            low_bound = search_for;
+        }
+    }
+    let best_balance = up_bound;
+    let mut partitions: Vec<Range<usize>> = Vec::with_capacity(n_partitions);
+    let mut sum_part = 0;
+    let mut left = 0;
+    weights.iter().enumerate().for_each(|(i, w)| {
+        sum_part += w;
+        if sum_part > best_balance {
+            partitions.push(left..i);
+            sum_part = *w;
+            left = i;
+        }
+    });
+    partitions.push(left..n_items);
+
+    partitions
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn largest_part(weights: &[usize], partitions: &[Range<usize>]) -> usize {
+        partitions.iter().map(|p| weights[p.clone()].iter().sum()).max().unwrap_or(0)
+    }
+
+    // smallest achievable largest part, by trying every first cut.
+    fn brute_force(weights: &[usize], n_partitions: usize) -> usize {
+        if n_partitions == 1 || weights.len() <= 1 {
+            return weights.iter().sum();
+        }
+        (1..=weights.len())
+            .map(|cut| {
+                let first: usize = weights[..cut].iter().sum();
+                cmp::max(first, brute_force(&weights[cut..], n_partitions - 1))
+            })
+            .min()
+            .unwrap()
+    }
+
+    fn check(weights: &[usize], n_partitions: usize) {
+        let partitions = optimal_contiguous_partition(weights, n_partitions);
+        assert!(partitions.len() <= n_partitions);
+        assert_eq!(partitions[0].start, 0);
+        assert_eq!(partitions.last().unwrap().end, weights.len());
+        for pair in partitions.windows(2) {
+            assert_eq!(pair[0].end, pair[1].start);
+        }
+        if weights.iter().sum::<usize>() > 0 {
+            assert!(partitions.iter().all(|p| !p.is_empty()));
+        }
+        assert_eq!(largest_part(weights, &partitions), brute_force(weights, n_partitions));
+    }
+
+    #[test]
+    fn partitions_are_optimal() {
+        check(&[1, 2, 3, 4, 5, 6, 7, 8, 9], 3);
+        check(&[9, 1, 1, 1, 1, 1, 1, 1, 1], 3);
+        check(&[1, 1, 1, 1, 1, 1, 1, 1, 100], 4);
+        check(&[5, 5, 5, 5], 4);
+        check(&[5, 5, 5, 5], 8);
+        check(&[0, 0, 7, 0, 0, 3, 0], 2);
+        check(&[3, 1, 4, 1, 5, 9, 2, 6, 5, 3], 1);
+        let mut x = 17usize;
+        for n_partitions in 1..6 {
+            let weights: Vec<usize> = (0..10).map(|_| { x = (x * 31 + 7) % 101; x % 13 }).collect();
+            check(&weights, n_partitions);
+        }
+    }
+
+    #[test]
+    fn thread_count() {
+        let (nthreads, nparts) = with_threads(Some(3), || (rayon::current_num_threads(), n_partitions())).unwrap();
+        assert_eq!((nthreads, nparts), (3, 3 * OVERSUBSCRIPTION));
+        let edges: Vec<(crate::Vertex, crate::Vertex)> = (0..40).flat_map(|u| ((u + 1)..40).map(move |v| (u, v))).collect();
+        let g = crate::StaticGraph::from_edges(40, edges);
+        for nthreads in 1..5 {
+            assert_eq!(with_threads(Some(nthreads), || crate::triangles::threaded_triangles(&g)).unwrap(), 9880);
+        }
+    }
+
+    #[test]
+    fn degenerate_weights() {
+        assert_eq!(optimal_contiguous_partition(&[], 4), vec![0..0]);
+        assert_eq!(optimal_contiguous_partition(&[0, 0, 0], 2), vec![0..3]);
+        assert_eq!(optimal_contiguous_partition(&[10], 2), vec![0..1]);
+    }
+}
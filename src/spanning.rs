@@ -0,0 +1,252 @@
+use std::cmp::Reverse;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use ordered_float::OrderedFloat;
+use priority_queue::PriorityQueue;
+use rayon::prelude::*;
+
+use num::cast::AsPrimitive;
+use num::traits::PrimInt;
+use crate::disjoint_set::DisjointSet;
+use crate::traits::Graph;
+
+/// A minimum spanning forest: a minimum spanning tree of every connected
+/// component.
+pub struct SpanningForest<V, W> {
+    /// Edges of the forest as (u, v) with u < v.
+    pub edges: Vec<(V, V)>,
+    pub weight: W,
+}
+
+// edges are compared by weight, then by endpoints, so that the minimum
+// spanning forest is unique and all algorithms return the same one.
+type EdgeKey<V, W> = (OrderedFloat<W>, V, V);
+
+// every edge (u, v, weight) of an undirected graph with u < v, in parallel.
+fn weighted_edges<V, G, W>(g: &G, weights: fn(V, V) -> W) -> Vec<EdgeKey<V, W>>
+where
+    G: Graph<V> + Sync,
+    V: PrimInt + AsPrimitive<usize> + Send + Sync,
+    usize: AsPrimitive<V>,
+    W: num::Float + Send,
+{
+    (0..g.nv().as_()).into_par_iter().flat_map(|uu| {
+        let u: V = uu.as_();
+        g.out_neighbors(u).iter()
+            .filter(|v| **v > u)
+            .map(|v| {
+                let w = weights(u, *v);
+                assert!(!w.is_nan(), "weights must not be NaN");
+                (OrderedFloat(w), u, *v)
+            })
+            .collect::<Vec<_>>()
+    }).collect()
+}
+
+fn forest<V, W: num::Float>(keys: Vec<EdgeKey<V, W>>) -> SpanningForest<V, W> {
+    let weight = keys.iter().fold(W::zero(), |acc, k| acc + k.0.into_inner());
+    SpanningForest { edges: keys.into_iter().map(|k| (k.1, k.2)).collect(), weight }
+}
+
+/// Kruskal's minimum spanning forest of an undirected graph. Edges are
+/// returned in nondecreasing weight order.
+pub fn kruskal_mst<V, G, W>(g: &G, weights: fn(V, V) -> W) -> SpanningForest<V, W>
+where
+    G: Graph<V> + Sync,
+    V: PrimInt + AsPrimitive<usize> + Send + Sync,
+    usize: AsPrimitive<V>,
+    W: num::Float + Send,
+{
+    let mut edges = weighted_edges(g, weights);
+    edges.par_sort_unstable();
+    let mut ds = DisjointSet::new(g.nv());
+    let target = g.nv().as_().saturating_sub(1);
+    let mut kept = Vec::with_capacity(target);
+    for e in edges {
+        if ds.union(e.1, e.2) {
+            kept.push(e);
+            if kept.len() == target {
+                break;
+            }
+        }
+    }
+    forest(kept)
+}
+
+/// Prim's minimum spanning forest of an undirected graph, growing a tree from
+/// the lowest unvisited vertex of every component. Edges are returned in the
+/// order they were added.
+pub fn prim_mst<V, W>(g: &impl Graph<V>, weights: fn(V, V) -> W) -> SpanningForest<V, W>
+where
+    V: PrimInt + AsPrimitive<usize> + std::hash::Hash + std::cmp::Eq,
+    W: num::Float,
+{
+    let n = g.nv().as_();
+    let mut visited = vec![false; n];
+    // the lightest known edge to each vertex adjacent to the tree.
+    let mut pq = PriorityQueue::<V, Reverse<EdgeKey<V, W>>>::new();
+    let mut kept: Vec<EdgeKey<V, W>> = Vec::new();
+    for root in g.vertices() {
+        if visited[root.as_()] {
+            continue;
+        }
+        visited[root.as_()] = true;
+        let mut u = root;
+        loop {
+            for v in g.out_neighbors(u) {
+                if visited[v.as_()] {
+                    continue;
+                }
+                let w = weights(u, *v);
+                assert!(!w.is_nan(), "weights must not be NaN");
+                let key = if u < *v { (OrderedFloat(w), u, *v) } else { (OrderedFloat(w), *v, u) };
+                let better = match pq.get_priority(v) {
+                    Some(Reverse(k)) => key < *k,
+                    None => true,
+                };
+                if better {
+                    pq.push(*v, Reverse(key));
+                }
+            }
+            match pq.pop() {
+                Some((v, Reverse(key))) => {
+                    visited[v.as_()] = true;
+                    kept.push(key);
+                    u = v;
+                }
+                None => break,
+            }
+        }
+    }
+    forest(kept)
+}
+
+// lowers `a` to `x` if `x` is smaller.
+fn atomic_min(a: &AtomicUsize, x: usize) {
+    let mut cur = a.load(Ordering::Relaxed);
+    while x < cur {
+        match a.compare_exchange(cur, x, Ordering::Relaxed, Ordering::Relaxed) {
+            Ok(_) => return,
+            Err(c) => cur = c,
+        }
+    }
+}
+
+/// Parallel Borůvka minimum spanning forest of an undirected graph. In each
+/// round every component picks its lightest outgoing edge in parallel, the
+/// picked edges are added and the components they join are merged, until no
+/// edge joins two components. Edges are returned in the order they were
+/// added.
+pub fn parallel_boruvka_mst<V, G, W>(g: &G, weights: fn(V, V) -> W) -> SpanningForest<V, W>
+where
+    G: Graph<V> + Sync,
+    V: PrimInt + AsPrimitive<usize> + Send + Sync,
+    usize: AsPrimitive<V>,
+    W: num::Float + Send + Sync,
+{
+    let n = g.nv().as_();
+    let mut edges = weighted_edges(g, weights);
+    edges.par_sort_unstable();
+    // edges are identified by their rank, so the lightest is the lowest.
+    let mut alive: Vec<usize> = (0..edges.len()).collect();
+    let mut comp: Vec<usize> = (0..n).collect();
+    let lightest: Vec<AtomicUsize> = (0..n).map(|_| AtomicUsize::new(usize::MAX)).collect();
+    let mut kept: Vec<EdgeKey<V, W>> = Vec::new();
+    while !alive.is_empty() {
+        alive.par_iter().for_each(|i| {
+            let (_, u, v) = edges[*i];
+            atomic_min(&lightest[comp[u.as_()]], *i);
+            atomic_min(&lightest[comp[v.as_()]], *i);
+        });
+        let mut picked: Vec<usize> = lightest.par_iter()
+            .map(|l| l.swap(usize::MAX, Ordering::Relaxed))
+            .filter(|i| *i != usize::MAX)
+            .collect();
+        picked.par_sort_unstable();
+        picked.dedup();
+
+        // merge the components joined by the picked edges, which form a
+        // forest over the components.
+        let mut ds = DisjointSet::<usize>::new(n);
+        for i in picked.iter() {
+            let (_, u, v) = edges[*i];
+            ds.union(comp[u.as_()], comp[v.as_()]);
+            kept.push(edges[*i]);
+        }
+        let roots: Vec<usize> = (0..n).map(|c| ds.find(c)).collect();
+        comp.par_iter_mut().for_each(|c| *c = roots[*c]);
+        alive = alive.into_par_iter()
+            .filter(|i| {
+                let (_, u, v) = edges[*i];
+                comp[u.as_()] != comp[v.as_()]
+            })
+            .collect();
+    }
+    forest(kept)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::random_graph;
+    use crate::{StaticGraph, Vertex};
+
+    // few distinct values, so that ties between edges are common.
+    fn weight(u: Vertex, v: Vertex) -> f64 {
+        ((u * 7 + v * 7) % 5) as f64
+    }
+
+    fn sorted_edges(f: &SpanningForest<Vertex, f64>) -> Vec<(Vertex, Vertex)> {
+        let mut edges = f.edges.clone();
+        edges.sort_unstable();
+        edges
+    }
+
+    fn check(g: &StaticGraph) -> SpanningForest<Vertex, f64> {
+        let kruskal = kruskal_mst(g, weight);
+        let prim = prim_mst(g, weight);
+        let boruvka = parallel_boruvka_mst(g, weight);
+        assert_eq!(sorted_edges(&prim), sorted_edges(&kruskal));
+        assert_eq!(sorted_edges(&boruvka), sorted_edges(&kruskal));
+        assert_eq!(prim.weight, kruskal.weight);
+        assert_eq!(boruvka.weight, kruskal.weight);
+        assert!(kruskal.edges.iter().all(|(u, v)| u < v && g.has_edge(*u, *v)));
+        kruskal
+    }
+
+    #[test]
+    fn weighted_graph() {
+        // a square with a heavy diagonal and a self-loop.
+        fn square(u: Vertex, v: Vertex) -> f64 {
+            match (u.min(v), u.max(v)) {
+                (0, 2) => 10.0,
+                (0, 3) => 4.0,
+                (u, v) => (u + v) as f64,
+            }
+        }
+        let g = StaticGraph::from_edges(4, vec![(0, 1), (1, 2), (2, 3), (3, 0), (0, 2), (1, 1)]);
+        for f in [kruskal_mst(&g, square), prim_mst(&g, square), parallel_boruvka_mst(&g, square)].iter() {
+            let mut edges = f.edges.clone();
+            edges.sort_unstable();
+            assert_eq!(edges, vec![(0, 1), (0, 3), (1, 2)]);
+            assert_eq!(f.weight, 8.0);
+        }
+        for seed in 0..10 {
+            let forest = check(&random_graph(80, 400, seed));
+            assert_eq!(forest.edges.len(), 79);
+        }
+    }
+
+    #[test]
+    fn disconnected_graph() {
+        // a triangle, an edge and an isolated vertex.
+        let g = StaticGraph::from_edges(6, vec![(0, 1), (1, 2), (2, 0), (3, 4)]);
+        assert_eq!(check(&g).edges.len(), 3);
+        for seed in 0..10 {
+            let g = random_graph(200, 150, seed);
+            let forest = check(&g);
+            let (_, sizes) = crate::components::connected_components(&g);
+            assert_eq!(forest.edges.len(), 200 - sizes.len());
+        }
+        assert!(check(&StaticGraph::from_edges(3, vec![])).edges.is_empty());
+    }
+}
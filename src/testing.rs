@@ -0,0 +1,50 @@
+// Seeded random graphs shared by the unit tests.
+use std::collections::HashSet;
+
+use crate::{StaticDiGraph, StaticGraph, Vertex};
+
+// SplitMix64: good enough for test inputs, and the same on every platform.
+struct SplitMix64(u64);
+
+impl SplitMix64 {
+    fn next(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut x = self.0;
+        x = (x ^ (x >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        x = (x ^ (x >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        x ^ (x >> 31)
+    }
+}
+
+// `m` distinct edges between distinct vertices of `0..n`, chosen uniformly at
+// random. Undirected edges are returned once, with the lower endpoint first.
+pub fn random_edges(n: Vertex, m: usize, directed: bool, seed: u64) -> Vec<(Vertex, Vertex)> {
+    let npairs = n as usize * (n as usize).saturating_sub(1);
+    let npairs = if directed { npairs } else { npairs / 2 };
+    assert!(m <= npairs, "too many edges for {} vertices", n);
+    let mut rng = SplitMix64(seed);
+    let mut seen: HashSet<(Vertex, Vertex)> = HashSet::with_capacity(m);
+    let mut edgelist: Vec<(Vertex, Vertex)> = Vec::with_capacity(m);
+    while edgelist.len() < m {
+        let u = (rng.next() % n as u64) as Vertex;
+        let v = (rng.next() % n as u64) as Vertex;
+        if u == v {
+            continue;
+        }
+        let e = if directed || u < v { (u, v) } else { (v, u) };
+        if seen.insert(e) {
+            edgelist.push(e);
+        }
+    }
+    edgelist
+}
+
+// an undirected graph with `n` vertices and `m` random edges.
+pub fn random_graph(n: Vertex, m: usize, seed: u64) -> StaticGraph {
+    StaticGraph::from_edges(n, random_edges(n, m, false, seed))
+}
+
+// a directed graph with `n` vertices and `m` random edges.
+pub fn random_digraph(n: Vertex, m: usize, seed: u64) -> StaticDiGraph {
+    StaticDiGraph::from_edges(n, random_edges(n, m, true, seed))
+}
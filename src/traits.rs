@@ -2,6 +2,7 @@ use std::fs::File;
 use std::error::Error;
 use std::path::Path;
 use std::io::{BufRead, BufReader};
+use num::cast::AsPrimitive;
 
 pub trait Graph<V>: Sized {
     type VIterator: Iterator<Item=V>;
@@ -20,4 +21,17 @@ pub trait Graph<V>: Sized {
         let file = BufReader::new(&f);
         Self::from_edge_reader(file)
     }
+    /// Position of the first out-edge of each vertex in CSR edge order, so
+    /// that edge `(u, out_neighbors(u)[i])` has index `offsets[u] + i`. The
+    /// last element is `ne()`.
+    fn edge_offsets(&self) -> Vec<usize> where V: AsPrimitive<usize> {
+        let mut offsets = Vec::with_capacity(self.nv().as_() + 1);
+        let mut acc = 0usize;
+        offsets.push(acc);
+        for v in self.vertices() {
+            acc += self.out_degree(v).as_();
+            offsets.push(acc);
+        }
+        offsets
+    }
 }
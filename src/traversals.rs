@@ -1,16 +1,28 @@
+use std::cmp::Reverse;
 use std::mem;
 use bitvec::prelude as bv;
 use ordered_float::OrderedFloat;
 use priority_queue::PriorityQueue;
+use rayon::prelude::*;
 use crate::traits::Graph;
 use num::cast::AsPrimitive;
 use num::traits::PrimInt;
 
 pub fn bfs<V>(g: &impl Graph<V>, src: V) -> Vec<V> where V:PrimInt + AsPrimitive<usize> {
+    bfs_levels(g, src, false)
+}
+
+/// Like `bfs`, but following edges backwards: returns the distance from every
+/// vertex to `src`.
+pub fn reverse_bfs<V>(g: &impl Graph<V>, src: V) -> Vec<V> where V:PrimInt + AsPrimitive<usize> {
+    bfs_levels(g, src, true)
+}
+
+fn bfs_levels<V>(g: &impl Graph<V>, src: V, reverse: bool) -> Vec<V> where V:PrimInt + AsPrimitive<usize> {
     let n = g.nv();
     let maxdeg = g
         .vertices()
-        .map(|v| g.out_degree(v))
+        .map(|v| if reverse { g.in_degree(v) } else { g.out_degree(v) })
         .max()
         .expect("Invalid degree found")
         .as_();
@@ -33,7 +45,8 @@ pub fn bfs<V>(g: &impl Graph<V>, src: V) -> Vec<V> where V:PrimInt + AsPrimitive
     // println!("cur_level = {:?}", cur_level);
     while !cur_level.is_empty() {
         for v in cur_level.iter() {
-            for i in g.out_neighbors(*v) {
+            let nbrs = if reverse { g.in_neighbors(*v) } else { g.out_neighbors(*v) };
+            for i in nbrs {
                 // println!("neighbor {:?}", i);
                 let ui = i.as_();
                 if unsafe { !*visited.get_unchecked(ui) } {
@@ -55,6 +68,23 @@ pub fn bfs<V>(g: &impl Graph<V>, src: V) -> Vec<V> where V:PrimInt + AsPrimitive
     }
     levels
 }
+/// Runs `bfs` from every vertex in parallel and returns `f(src, levels)` for
+/// each source.
+pub fn all_sources_bfs_map<V, G, T, F>(g: &G, f: F) -> Vec<T>
+where
+    G: Graph<V> + Sync,
+    V: PrimInt + AsPrimitive<usize> + Send + Sync,
+    usize: AsPrimitive<V>,
+    T: Send,
+    F: Fn(V, &[V]) -> T + Sync + Send,
+{
+    (0..g.nv().as_()).into_par_iter().map(|s| {
+        let src: V = s.as_();
+        let levels = bfs(g, src);
+        f(src, &levels)
+    }).collect()
+}
+
 pub fn dijkstra<V, W>(g: &impl Graph<V>, v: V, weights: fn(V, V) -> W) -> Vec<W>
 where
     V: PrimInt + AsPrimitive<usize> + std::hash::Hash + std::cmp::Eq,
@@ -101,3 +131,62 @@ where
     dists
 }
 
+
+/// Shortest paths from a single source, with the data needed by Brandes'
+/// betweenness algorithm.
+pub struct ShortestPaths<V, W> {
+    /// Reached vertices, in nondecreasing distance from the source.
+    pub order: Vec<V>,
+    pub dists: Vec<W>,
+    /// Number of shortest paths from the source to each vertex.
+    pub sigma: Vec<f64>,
+    /// Predecessors of each vertex on its shortest paths.
+    pub preds: Vec<Vec<V>>,
+}
+
+/// Dijkstra's algorithm, also counting shortest paths and recording their
+/// predecessors. Weights must be positive.
+pub fn dijkstra_shortest_paths<V, W>(g: &impl Graph<V>, src: V, weights: fn(V, V) -> W) -> ShortestPaths<V, W>
+where
+    V: PrimInt + AsPrimitive<usize> + std::hash::Hash + std::cmp::Eq,
+    W: num::Float,
+{
+    let n = g.nv().as_();
+    let mut settled: bv::BitVec<bv::Lsb0, u64> = bv::BitVec::repeat(false, n);
+    let mut pq = PriorityQueue::<V, Reverse<OrderedFloat<W>>>::new();
+    let mut order: Vec<V> = Vec::new();
+    let mut dists = vec![W::infinity(); n];
+    let mut sigma = vec![0.0f64; n];
+    let mut preds: Vec<Vec<V>> = vec![Vec::new(); n];
+
+    dists[src.as_()] = W::zero();
+    sigma[src.as_()] = 1.0;
+    pq.push(src, Reverse(OrderedFloat(W::zero())));
+    while let Some((u, Reverse(OrderedFloat(d)))) = pq.pop() {
+        let uu = u.as_();
+        if settled[uu] {
+            continue;
+        }
+        settled.set(uu, true);
+        order.push(u);
+        for v in g.out_neighbors(u) {
+            let vu = (*v).as_();
+            if settled[vu] {
+                continue;
+            }
+            let alt = d + weights(u, *v);
+            if alt < dists[vu] {
+                dists[vu] = alt;
+                sigma[vu] = sigma[uu];
+                preds[vu].clear();
+                preds[vu].push(u);
+                // updates the priority if v is already queued.
+                pq.push(*v, Reverse(OrderedFloat(alt)));
+            } else if alt == dists[vu] {
+                sigma[vu] += sigma[uu];
+                preds[vu].push(u);
+            }
+        }
+    }
+    ShortestPaths { order, dists, sigma, preds }
+}
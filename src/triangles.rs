@@ -1,43 +1,81 @@
-use std::ops::Range;
+use std::sync::atomic::{AtomicUsize, Ordering};
 use rayon::prelude::*;
+use bitvec::prelude as bv;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
 
 use num::cast::AsPrimitive;
 use num::traits::PrimInt;
+use crate::parallel::{n_partitions, optimal_contiguous_partition};
 use crate::traits::Graph;
-use crate::{StaticGraph, Vertex};
 
-pub fn triangles<V>(g: &impl Graph<V>) -> (u128, u128) where V:PrimInt + AsPrimitive<usize> {
+// orients every edge from the lower to the higher (degree, index) endpoint.
+// Returns the degrees and the out-neighbors of each vertex in the DAG.
+fn degree_ordered_lists<V>(g: &impl Graph<V>) -> (Vec<V>, Vec<Vec<V>>) where V:PrimInt + AsPrimitive<usize> {
     let mut dodg: Vec<Vec<V>> = Vec::with_capacity(g.nv().as_());
-
     let mut degrees = vec![V::zero(); g.nv().as_()];
-    let mut ntri = 0u128;
-    let mut nwedge = 0u128;
 
     // let z:Vec<V> = g.vertices().collect();
     // println!("minvert = {:?}, maxvert = {:?}", z.iter().min(), z.iter().max());
     for u in g.vertices() {
         let degu = g.out_degree(u);
         degrees[u.as_()] = degu;
-        let vvec = g.out_neighbors(u).into_iter().filter(|v| {
+        let vvec = g.out_neighbors(u).iter().filter(|v| {
             let degv = g.out_degree(**v);
             degv > degu || (degv == degu && **v > u)
         }).cloned();
         dodg.push(vvec.collect());
     }
+    (degrees, dodg)
+}
+
+// whether the edge between v and w, two DAG neighbors of the same vertex, is
+// in the DAG. It is stored in the adjacency list of the lower endpoint.
+fn dag_has_edge<V>(degrees: &[V], v: V, vvec: &[V], w: V, wvec: &[V]) -> bool
+where
+    V: PrimInt + AsPrimitive<usize>,
+{
+    let (vv, ww) = (v.as_(), w.as_());
+    if degrees[vv] > degrees[ww] || (degrees[vv] == degrees[ww] && v > w) {
+        wvec.binary_search(&v).is_ok()
+    } else {
+        vvec.binary_search(&w).is_ok()
+    }
+}
+
+// number of triangles in a DAG whose lists are subsets of those built by
+// `degree_ordered_lists`, counted in parallel over vertices.
+fn dag_triangles<V>(degrees: &[V], dodg: &[Vec<V>]) -> u128
+where
+    V: PrimInt + AsPrimitive<usize> + Send + Sync,
+{
+    dodg.par_iter().map(|uvec| {
+        let mut ntri = 0u128;
+        for (i, &v) in uvec.iter().enumerate() {
+            let vvec = &dodg[v.as_()];
+            for &w in uvec[(i+1)..].iter() {
+                if dag_has_edge(degrees, v, vvec, w, &dodg[w.as_()]) {
+                    ntri += 1;
+                }
+            }
+        }
+        ntri
+    }).sum()
+}
+
+pub fn triangles<V>(g: &impl Graph<V>) -> (u128, u128) where V:PrimInt + AsPrimitive<usize> {
+    let (degrees, dodg) = degree_ordered_lists(g);
+    let mut ntri = 0u128;
+    let mut nwedge = 0u128;
 
     // println!("len(dodg) = {}", dodg.len());
     for u in g.vertices() {
         let uvec = &dodg[u.as_()];
-        let ulen = uvec.len();
-        for i in 0..ulen {
-            let v = uvec[i];
+        for (i, &v) in uvec.iter().enumerate() {
             let vvec = &dodg[v.as_()];
-            for j in (i+1)..ulen {
+            for &w in uvec[(i+1)..].iter() {
                 nwedge += 1;
-                let w = uvec[j];
-                let wvec = &dodg[w.as_()];
-                let w_to_v = degrees[v.as_()] > degrees[w.as_()] || (degrees[v.as_()] == degrees[w.as_()] && v > w);
-                if (w_to_v && wvec.binary_search(&v).is_ok()) || (!w_to_v && vvec.binary_search(&w).is_ok()) {
+                if dag_has_edge(&degrees, v, vvec, w, &dodg[w.as_()]) {
                      ntri += 1;
                 }
             }
@@ -46,62 +84,41 @@ pub fn triangles<V>(g: &impl Graph<V>) -> (u128, u128) where V:PrimInt + AsPrimi
     (ntri, nwedge)
 }
 
-fn optimal_contiguous_partition(weights: Vec<usize>, n_partitions: usize) -> Vec<Range<usize>>
-{
+// the degree-ordered DAG in CSR form.
+pub(crate) struct Dodg<V> {
+    indptr: Vec<usize>,
+    indices: Vec<V>,
+}
 
-    let mut up_bound = weights.iter().sum();
-    let mut low_bound = (up_bound-1) / n_partitions;
-    let n_items = weights.len();
-
-    while up_bound > low_bound + 1 {
-        let search_for = (up_bound + low_bound) / 2;
-        let mut sum_part = 0usize;
-        let mut remain_part = n_partitions;
-        let mut possible = true;
-
-        for w in weights.iter() {
-            sum_part += w;
-            if sum_part > search_for {
-                sum_part = *w;
-                remain_part -= 1;
-                if remain_part == 0 {
-                    possible = false;
-                    break;
-                }
-            }
-        }
-        if possible {
-            up_bound = search_for;
-        } else {
-            low_bound = search_for;
-        }
+impl<V> Dodg<V> {
+    pub(crate) fn dim(&self) -> usize {
+        self.indptr.len() - 1
     }
-    let best_balance = up_bound;
-    let mut partitions: Vec<Range<usize>> = Vec::with_capacity(n_partitions);
-    let mut sum_part = 0;
-    let mut left = 0;
-    weights.iter().enumerate().for_each(|(i, w)| {
-        sum_part += w;
-        if sum_part > best_balance {
-            partitions.push(left..i);
-            sum_part = *w;
-            left = i;
-        }
-    });
-    partitions.push(left..n_items);
-
-    partitions
-}
 
+    pub(crate) fn row_len(&self, r: usize) -> usize {
+        self.indptr[r + 1] - self.indptr[r]
+    }
 
+    pub(crate) fn row(&self, r: usize) -> &[V] {
+        &self.indices[self.indptr[r]..self.indptr[r + 1]]
+    }
+}
 
-pub fn threaded_triangles(g: &StaticGraph) -> u128 {
+// orients every edge from the lower to the higher (degree, index) endpoint.
+// Returns the degrees and the resulting DAG.
+pub(crate) fn degree_ordered_dag<V, G>(g: &G) -> (Vec<V>, Dodg<V>)
+where
+    G: Graph<V> + Sync,
+    V: PrimInt + AsPrimitive<usize> + Send + Sync,
+    usize: AsPrimitive<V>,
+{
     // let z:Vec<V> = g.vertices().collect();
     // println!("minvert = {:?}, maxvert = {:?}", z.iter().min(), z.iter().max());
-    let mut bigvec: Vec<(Vertex, Vec<Vertex>)> = Vec::with_capacity(g.nv().as_());
-    (0..g.nv()).into_par_iter().map(|u| {
+    let mut bigvec: Vec<(V, Vec<V>)> = Vec::with_capacity(g.nv().as_());
+    (0..g.nv().as_()).into_par_iter().map(|u| {
+        let u: V = u.as_();
         let degu = g.out_degree(u);
-        let vvec: Vec<Vertex> = g.out_neighbors(u).into_iter().filter(|v| {
+        let vvec: Vec<V> = g.out_neighbors(u).iter().filter(|v| {
             let degv = g.out_degree(**v);
             degv > degu || (degv == degu && **v > u)
         }).cloned().collect();
@@ -115,50 +132,40 @@ pub fn threaded_triangles(g: &StaticGraph) -> u128 {
     // let ss: usize = s.into_iter().sum();
     // println!("total sum from bigvec = {}", ss);
 
-    let (degrees, dodg) = {
-        let mut degs: Vec<Vertex> = Vec::with_capacity(bigvec.len());
-        let mut indptr: Vec<usize> = Vec::with_capacity(bigvec.len() + 1);
-        let mut indices: Vec<Vertex> = Vec::new();
-        // let mut acc = 0usize;
-        indptr.push(0);
-        bigvec.into_iter().for_each(|mut v| {
-            degs.push(v.0);
-            indices.append(&mut v.1);
-            indptr.push(indices.len());
-        });
-    // let isum: usize = indptr.iter().sum();
-    // println!("isum = {}", isum);
-    // println!("indices = {:?}", &indices[0..10]);
-    let gm = graph_matrix::GraphMatrix::new(indptr, indices);
-    // println!("gm = {}", gm);
-    (degs, gm)
-    };
+    let mut degs: Vec<V> = Vec::with_capacity(bigvec.len());
+    let mut indptr: Vec<usize> = Vec::with_capacity(bigvec.len() + 1);
+    let mut indices: Vec<V> = Vec::new();
+    indptr.push(0);
+    bigvec.into_iter().for_each(|mut v| {
+        degs.push(v.0);
+        indices.append(&mut v.1);
+        indptr.push(indices.len());
+    });
+    (degs, Dodg { indptr, indices })
+}
+
+pub fn threaded_triangles<V, G>(g: &G) -> u128
+where
+    G: Graph<V> + Sync,
+    V: PrimInt + AsPrimitive<usize> + Send + Sync,
+    usize: AsPrimitive<V>,
+{
+    let (degrees, dodg) = degree_ordered_dag(g);
 
-    // let (degrees, dodg): (Vec<V>, Vec<Vec<V>>) = bigvec.unzip();
     // let foo: usize = (0..dodg.dim()).map(|r| dodg.row_len(r).as_()).sum();
     // println!("foo = {}", foo);
-    // println!("dodg = {}", dodg);
-    let weights: Vec<usize> = (0..dodg.dim()).map(|r| dodg.row_len(r).pow(2).as_()).collect();
+    let weights: Vec<usize> = (0..dodg.dim()).map(|r| dodg.row_len(r).pow(2)).collect();
     // println!("{:?}", weights);
-    let partitions = optimal_contiguous_partition(weights, 12);
+    let partitions = optimal_contiguous_partition(&weights, n_partitions());
     // println!("{:?}", partitions);
     partitions.into_par_iter().map(|p| {
         let mut ntri = 0u128;
         for u in p {
-            let uu = u as Vertex;
-            let uvec = dodg.row(uu);
-            let ulen = uvec.len();
-            for i in 0..ulen {
-                let v = uvec[i];
-                let vv = v as usize;
-                let vvec = dodg.row(v);
-                for j in (i+1)..ulen {
-                    // ntri += 1;
-                    let w = uvec[j];
-                    let ww = w as usize;
-                    let wvec = dodg.row(w);
-                    let w_to_v = degrees[vv] > degrees[ww] || (degrees[vv] == degrees[ww] && v > w);
-                    if (w_to_v && wvec.binary_search(&v).is_ok()) || (!w_to_v && vvec.binary_search(&w).is_ok()) {
+            let uvec = dodg.row(u);
+            for (i, &v) in uvec.iter().enumerate() {
+                let vvec = dodg.row(v.as_());
+                for &w in uvec[(i+1)..].iter() {
+                    if dag_has_edge(&degrees, v, vvec, w, dodg.row(w.as_())) {
                          ntri += 1;
                     }
                 }
@@ -168,12 +175,18 @@ pub fn threaded_triangles(g: &StaticGraph) -> u128 {
     }).sum()
 }
 
-pub fn threaded_triangles_csr(g: &StaticGraph) -> u128 {
+pub fn threaded_triangles_csr<V, G>(g: &G) -> u128
+where
+    G: Graph<V> + Sync,
+    V: PrimInt + AsPrimitive<usize> + Send + Sync,
+    usize: AsPrimitive<V>,
+{
     // let z:Vec<V> = g.vertices().collect();
     // println!("minvert = {:?}, maxvert = {:?}", z.iter().min(), z.iter().max());
-    let bigvec = (0..g.nv()).into_par_iter().map(|u| {
+    let bigvec = (0..g.nv().as_()).into_par_iter().map(|u| {
+        let u: V = u.as_();
         let degu = g.out_degree(u);
-        let vvec: Vec<Vertex> = g.out_neighbors(u).into_iter().filter(|v| {
+        let vvec: Vec<V> = g.out_neighbors(u).iter().filter(|v| {
             let degv = g.out_degree(**v);
             degv > degu || (degv == degu && **v > u)
         }).cloned().collect();
@@ -187,32 +200,23 @@ pub fn threaded_triangles_csr(g: &StaticGraph) -> u128 {
     // let ss: usize = s.into_iter().sum();
     // println!("total sum from bigvec = {}", ss);
 
-    let (degrees, dodg): (Vec<Vertex>, Vec<Vec<Vertex>>) = bigvec.unzip();
+    let (degrees, dodg): (Vec<V>, Vec<Vec<V>>) = bigvec.unzip();
 
-    // let (degrees, dodg): (Vec<V>, Vec<Vec<V>>) = bigvec.unzip();
     // let foo: usize = (0..dodg.dim()).map(|r| dodg.row_len(r).as_()).sum();
     // println!("foo = {}", foo);
     // println!("dodg = {}", dodg);
     let weights: Vec<usize> = (0..dodg.len()).map(|r| dodg[r].len()).collect();
     // println!("{:?}", weights);
-    let partitions = optimal_contiguous_partition(weights, 12);
+    let partitions = optimal_contiguous_partition(&weights, n_partitions());
     // println!("{:?}", partitions);
     partitions.into_par_iter().map(|p| {
         let mut ntri = 0u128;
         for u in p {
             let uvec = &dodg[u];
-            let ulen = uvec.len();
-            for i in 0..ulen {
-                let v = uvec[i];
-                let vv = v as usize;
-                let vvec = &dodg[vv];
-                for j in (i+1)..ulen {
-                    // ntri += 1;
-                    let w = uvec[j];
-                    let ww = w as usize;
-                    let wvec = &dodg[ww];
-                    let w_to_v = degrees[vv] > degrees[ww] || (degrees[vv] == degrees[ww] && v > w);
-                    if (w_to_v && wvec.binary_search(&v).is_ok()) || (!w_to_v && vvec.binary_search(&w).is_ok()) {
+            for (i, &v) in uvec.iter().enumerate() {
+                let vvec = &dodg[v.as_()];
+                for &w in uvec[(i+1)..].iter() {
+                    if dag_has_edge(&degrees, v, vvec, w, &dodg[w.as_()]) {
                          ntri += 1;
                     }
                 }
@@ -221,3 +225,614 @@ pub fn threaded_triangles_csr(g: &StaticGraph) -> u128 {
        ntri
     }).sum()
 }
+
+/// Number of triangles each vertex belongs to.
+pub fn threaded_vertex_triangles<V, G>(g: &G) -> Vec<usize>
+where
+    G: Graph<V> + Sync,
+    V: PrimInt + AsPrimitive<usize> + Send + Sync,
+    usize: AsPrimitive<V>,
+{
+    let (degrees, dodg) = degree_ordered_dag(g);
+    let counts: Vec<AtomicUsize> = (0..dodg.dim()).map(|_| AtomicUsize::new(0)).collect();
+    let weights: Vec<usize> = (0..dodg.dim()).map(|r| dodg.row_len(r).pow(2)).collect();
+    let partitions = optimal_contiguous_partition(&weights, n_partitions());
+    partitions.into_par_iter().for_each(|p| {
+        for u in p {
+            let uvec = dodg.row(u);
+            let mut ntri = 0usize;
+            for (i, &v) in uvec.iter().enumerate() {
+                let vvec = dodg.row(v.as_());
+                for &w in uvec[(i+1)..].iter() {
+                    if dag_has_edge(&degrees, v, vvec, w, dodg.row(w.as_())) {
+                        ntri += 1;
+                        counts[v.as_()].fetch_add(1, Ordering::Relaxed);
+                        counts[w.as_()].fetch_add(1, Ordering::Relaxed);
+                    }
+                }
+            }
+            counts[u].fetch_add(ntri, Ordering::Relaxed);
+        }
+    });
+    counts.into_iter().map(|c| c.into_inner()).collect()
+}
+
+/// Strategy used to intersect the sorted neighbor lists of the degree-ordered
+/// DAG in `threaded_triangles_with`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Intersection {
+    /// Binary search of each element of the shorter list in the longer one.
+    BinarySearch,
+    /// Linear merge of both lists.
+    Merge,
+    /// Exponential search of each element of the shorter list in the longer
+    /// one, resuming from the previous match.
+    Galloping,
+    /// Marks the neighbors of each vertex in a bitmap, then probes it with the
+    /// neighbors of each of its neighbors.
+    Bitmap,
+    /// Bitmap for vertices with at least `BITMAP_MIN_DEGREE` DAG neighbors,
+    /// otherwise galloping for lists whose lengths differ by at least a factor
+    /// `GALLOPING_MIN_RATIO`, and merge for the rest.
+    Auto,
+}
+
+pub const BITMAP_MIN_DEGREE: usize = 256;
+pub const GALLOPING_MIN_RATIO: usize = 32;
+
+impl Intersection {
+    pub const ALL: [Intersection; 5] = [
+        Intersection::BinarySearch,
+        Intersection::Merge,
+        Intersection::Galloping,
+        Intersection::Bitmap,
+        Intersection::Auto,
+    ];
+}
+
+fn intersect_binary_search<V: Ord>(small: &[V], large: &[V]) -> usize {
+    small.iter().filter(|x| large.binary_search(x).is_ok()).count()
+}
+
+fn intersect_merge<V: Ord>(a: &[V], b: &[V]) -> usize {
+    let mut count = 0;
+    let (mut i, mut j) = (0, 0);
+    while i < a.len() && j < b.len() {
+        if a[i] < b[j] {
+            i += 1;
+        } else if a[i] > b[j] {
+            j += 1;
+        } else {
+            count += 1;
+            i += 1;
+            j += 1;
+        }
+    }
+    count
+}
+
+fn intersect_galloping<V: Ord>(small: &[V], large: &[V]) -> usize {
+    let mut count = 0;
+    let mut lo = 0;
+    for x in small {
+        if lo >= large.len() {
+            break;
+        }
+        let mut step = 1;
+        while lo + step < large.len() && large[lo + step] < *x {
+            step *= 2;
+        }
+        let hi = std::cmp::min(lo + step + 1, large.len());
+        match large[lo..hi].binary_search(x) {
+            Ok(i) => {
+                count += 1;
+                lo += i + 1;
+            }
+            Err(i) => lo += i,
+        }
+    }
+    count
+}
+
+fn intersect<V: Ord>(method: Intersection, a: &[V], b: &[V]) -> usize {
+    let (small, large) = if a.len() <= b.len() { (a, b) } else { (b, a) };
+    match method {
+        Intersection::BinarySearch => intersect_binary_search(small, large),
+        Intersection::Merge => intersect_merge(small, large),
+        Intersection::Galloping => intersect_galloping(small, large),
+        Intersection::Bitmap | Intersection::Auto => {
+            if large.len() >= GALLOPING_MIN_RATIO * small.len() {
+                intersect_galloping(small, large)
+            } else {
+                intersect_merge(small, large)
+            }
+        }
+    }
+}
+
+/// Counts triangles by intersecting, for every edge `(u, v)` of the
+/// degree-ordered DAG, the DAG neighbors of `u` and `v` with `method`.
+pub fn threaded_triangles_with<V, G>(g: &G, method: Intersection) -> u128
+where
+    G: Graph<V> + Sync,
+    V: PrimInt + AsPrimitive<usize> + Send + Sync,
+    usize: AsPrimitive<V>,
+{
+    let (_, dodg) = degree_ordered_dag(g);
+    let n = dodg.dim();
+    let weights: Vec<usize> = (0..n).map(|r| dodg.row_len(r).pow(2)).collect();
+    let partitions = optimal_contiguous_partition(&weights, n_partitions());
+    partitions.into_par_iter().map(|p| {
+        // allocated on the first hub of this partition.
+        let mut marks: Option<bv::BitVec<bv::Lsb0, u64>> = None;
+        let mut ntri = 0u128;
+        for u in p {
+            let uvec = dodg.row(u);
+            let use_bitmap = method == Intersection::Bitmap
+                || (method == Intersection::Auto && uvec.len() >= BITMAP_MIN_DEGREE);
+            if use_bitmap {
+                let marks = marks.get_or_insert_with(|| bv::BitVec::repeat(false, n));
+                for v in uvec {
+                    marks.set(v.as_(), true);
+                }
+                for v in uvec {
+                    ntri += dodg.row(v.as_()).iter().filter(|w| marks[w.as_()]).count() as u128;
+                }
+                for v in uvec {
+                    marks.set(v.as_(), false);
+                }
+            } else {
+                for v in uvec {
+                    ntri += intersect(method, uvec, dodg.row(v.as_())) as u128;
+                }
+            }
+        }
+        ntri
+    }).sum()
+}
+
+// number of wedges (paths of length two) centered at v, ignoring self-loops.
+fn vertex_wedges<V>(g: &impl Graph<V>, v: V) -> usize where V: PrimInt {
+    let d = g.out_neighbors(v).iter().filter(|w| **w != v).count();
+    d * d.saturating_sub(1) / 2
+}
+
+/// Local clustering coefficient of each vertex: the fraction of its wedges
+/// that are closed. Vertices with degree below two have coefficient zero.
+pub fn local_clustering_coefficients<V, G>(g: &G) -> Vec<f64>
+where
+    G: Graph<V> + Sync,
+    V: PrimInt + AsPrimitive<usize> + Send + Sync,
+    usize: AsPrimitive<V>,
+{
+    let ntris = threaded_vertex_triangles(g);
+    g.vertices().map(|v| {
+        let nwedge = vertex_wedges(g, v);
+        if nwedge == 0 {
+            0.0
+        } else {
+            ntris[v.as_()] as f64 / nwedge as f64
+        }
+    }).collect()
+}
+
+/// Global transitivity: three times the number of triangles over the number
+/// of wedges centered at any vertex.
+pub fn transitivity<V, G>(g: &G) -> f64
+where
+    G: Graph<V> + Sync,
+    V: PrimInt + AsPrimitive<usize> + Send + Sync,
+    usize: AsPrimitive<V>,
+{
+    let ntri = threaded_triangles(g);
+    let nwedge: usize = (0..g.nv().as_()).into_par_iter().map(|v| vertex_wedges(g, v.as_())).sum();
+    if nwedge == 0 {
+        0.0
+    } else {
+        3.0 * ntri as f64 / nwedge as f64
+    }
+}
+
+// two-sided 95% normal quantile.
+const Z_95: f64 = 1.959_963_984_540_054;
+
+/// An estimated triangle count with a 95% confidence interval.
+#[derive(Clone, Copy, Debug)]
+pub struct TriangleEstimate {
+    pub estimate: f64,
+    pub lower: f64,
+    pub upper: f64,
+}
+
+/// Estimates the number of triangles by sampling `nsamples` wedges uniformly
+/// at random and checking which are closed.
+pub fn wedge_sampling_triangles<V>(g: &impl Graph<V>, nsamples: usize, seed: u64) -> TriangleEstimate
+where
+    V: PrimInt + AsPrimitive<usize>,
+    usize: AsPrimitive<V>,
+{
+    let mut rng = StdRng::seed_from_u64(seed);
+    let mut cumwedges: Vec<usize> = Vec::with_capacity(g.nv().as_());
+    let mut nwedge = 0usize;
+    for v in g.vertices() {
+        nwedge += vertex_wedges(g, v);
+        cumwedges.push(nwedge);
+    }
+    if nwedge == 0 || nsamples == 0 {
+        return TriangleEstimate { estimate: 0.0, lower: 0.0, upper: 0.0 };
+    }
+
+    let mut nclosed = 0usize;
+    for _ in 0..nsamples {
+        // centers are picked proportionally to their number of wedges.
+        let r = rng.gen_range(0, nwedge);
+        let (mut lo, mut hi) = (0, cumwedges.len());
+        while lo < hi {
+            let mid = (lo + hi) / 2;
+            if cumwedges[mid] <= r {
+                lo = mid + 1;
+            } else {
+                hi = mid;
+            }
+        }
+        let center: V = lo.as_();
+        let nbrs = g.out_neighbors(center);
+        let (v, w) = loop {
+            let i = rng.gen_range(0, nbrs.len());
+            let j = rng.gen_range(0, nbrs.len());
+            if i != j && nbrs[i] != center && nbrs[j] != center {
+                break (nbrs[i], nbrs[j]);
+            }
+        };
+        if g.has_edge(v, w) {
+            nclosed += 1;
+        }
+    }
+
+    let p = nclosed as f64 / nsamples as f64;
+    let halfwidth = Z_95 * (p * (1.0 - p) / nsamples as f64).sqrt();
+    let scale = nwedge as f64 / 3.0;
+    TriangleEstimate {
+        estimate: p * scale,
+        lower: (p - halfwidth).max(0.0) * scale,
+        upper: (p + halfwidth).min(1.0) * scale,
+    }
+}
+
+/// DOULION: estimates the number of triangles by keeping every edge with
+/// probability `p`, counting the triangles that remain and scaling by
+/// `1 / p^3`. The estimate is the mean over `ntrials` independent
+/// sparsifications, whose spread gives the confidence interval.
+pub fn doulion_triangles<V>(g: &impl Graph<V>, p: f64, ntrials: usize, seed: u64) -> TriangleEstimate
+where
+    V: PrimInt + AsPrimitive<usize> + Send + Sync,
+{
+    assert!(p > 0.0 && p <= 1.0, "edge probability must be in (0, 1]");
+    assert!(ntrials > 0, "at least one trial is required");
+    let mut rng = StdRng::seed_from_u64(seed);
+    let scale = 1.0 / (p * p * p);
+    // every edge appears once in the DAG, so sparsifying its lists samples
+    // the edges of g without building a new graph.
+    let (degrees, dodg) = degree_ordered_lists(g);
+    let estimates: Vec<f64> = (0..ntrials).map(|_| {
+        let sparse: Vec<Vec<V>> = dodg.iter().map(|vvec| {
+            vvec.iter().cloned().filter(|_| rng.gen_bool(p)).collect()
+        }).collect();
+        dag_triangles(&degrees, &sparse) as f64 * scale
+    }).collect();
+
+    let n = ntrials as f64;
+    let mean = estimates.iter().sum::<f64>() / n;
+    let halfwidth = if ntrials > 1 {
+        let var = estimates.iter().map(|x| (x - mean).powi(2)).sum::<f64>() / (n - 1.0);
+        Z_95 * (var / n).sqrt()
+    } else {
+        0.0
+    };
+    TriangleEstimate {
+        estimate: mean,
+        lower: (mean - halfwidth).max(0.0),
+        upper: mean + halfwidth,
+    }
+}
+
+fn sorted_triple<V: Ord>(u: V, v: V, w: V) -> (V, V, V) {
+    let (u, v) = if u < v { (u, v) } else { (v, u) };
+    if w < u {
+        (w, u, v)
+    } else if w < v {
+        (u, w, v)
+    } else {
+        (u, v, w)
+    }
+}
+
+/// Calls `f(u, v, w)` once for every triangle, in canonical form `u < v < w`.
+/// Triangles are visited in the order of the degree-ordered DAG rather than
+/// sorted, which would need them all in memory; sort them if order matters.
+pub fn for_each_triangle<V, F>(g: &impl Graph<V>, mut f: F)
+where
+    V: PrimInt + AsPrimitive<usize>,
+    F: FnMut(V, V, V),
+{
+    let (degrees, dodg) = degree_ordered_lists(g);
+    for u in g.vertices() {
+        let uvec = &dodg[u.as_()];
+        for (i, &v) in uvec.iter().enumerate() {
+            let vvec = &dodg[v.as_()];
+            for &w in uvec[(i+1)..].iter() {
+                if dag_has_edge(&degrees, v, vvec, w, &dodg[w.as_()]) {
+                    let (a, b, c) = sorted_triple(u, v, w);
+                    f(a, b, c);
+                }
+            }
+        }
+    }
+}
+
+/// Parallel version of `for_each_triangle`, passing each triangle in the same
+/// canonical form. `f` is called concurrently from several threads, so the
+/// order of the calls is not deterministic.
+pub fn par_for_each_triangle<V, G, F>(g: &G, f: F)
+where
+    G: Graph<V> + Sync,
+    V: PrimInt + AsPrimitive<usize> + Send + Sync,
+    usize: AsPrimitive<V>,
+    F: Fn(V, V, V) + Sync + Send,
+{
+    let (degrees, dodg) = degree_ordered_dag(g);
+    let weights: Vec<usize> = (0..dodg.dim()).map(|r| dodg.row_len(r).pow(2)).collect();
+    let partitions = optimal_contiguous_partition(&weights, n_partitions());
+    partitions.into_par_iter().for_each(|p| {
+        for u in p {
+            let uvec = dodg.row(u);
+            for (i, &v) in uvec.iter().enumerate() {
+                let vvec = dodg.row(v.as_());
+                for &w in uvec[(i+1)..].iter() {
+                    if dag_has_edge(&degrees, v, vvec, w, dodg.row(w.as_())) {
+                        let (a, b, c) = sorted_triple(u.as_(), v, w);
+                        f(a, b, c);
+                    }
+                }
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use std::error::Error;
+    use std::io::BufRead;
+    use std::sync::Mutex;
+    use super::*;
+    use crate::testing::random_graph;
+    use crate::{StaticGraph, Vertex};
+
+    // a minimal adjacency-list graph with 64-bit vertex ids.
+    struct VecGraph {
+        adj: Vec<Vec<u64>>,
+    }
+
+    impl Graph<u64> for VecGraph {
+        type VIterator = std::ops::Range<u64>;
+        fn nv(&self) -> u64 { self.adj.len() as u64 }
+        fn ne(&self) -> usize { self.adj.iter().map(|a| a.len()).sum() }
+        fn vertices(&self) -> Self::VIterator { 0..self.nv() }
+        fn in_degree(&self, v: u64) -> u64 { self.adj[v as usize].len() as u64 }
+        fn out_degree(&self, v: u64) -> u64 { self.adj[v as usize].len() as u64 }
+        fn in_neighbors(&self, v: u64) -> &[u64] { &self.adj[v as usize] }
+        fn out_neighbors(&self, v: u64) -> &[u64] { &self.adj[v as usize] }
+        fn has_edge(&self, u: u64, v: u64) -> bool { self.adj[u as usize].binary_search(&v).is_ok() }
+        fn from_edge_reader(reader: impl BufRead) -> Result<Self, Box<dyn Error>> {
+            let mut adj: Vec<Vec<u64>> = vec![];
+            for line in reader.lines() {
+                let line = line?;
+                let mut eit = line.split_whitespace();
+                let src: u64 = eit.next().ok_or("Invalid line (first field)")?.parse()?;
+                let dst: u64 = eit.next().ok_or("Invalid line (second field)")?.parse()?;
+                let n = std::cmp::max(src, dst) as usize + 1;
+                if adj.len() < n {
+                    adj.resize(n, vec![]);
+                }
+                adj[src as usize].push(dst);
+                adj[dst as usize].push(src);
+            }
+            for a in adj.iter_mut() {
+                a.sort_unstable();
+                a.dedup();
+            }
+            Ok(VecGraph { adj })
+        }
+    }
+
+    // two 4-cliques sharing an edge, a pendant triangle and a tail.
+    const EDGES: &str = "0 1\n0 2\n0 3\n1 2\n1 3\n2 3\n2 4\n3 4\n2 5\n3 5\n4 5\n5 6\n6 7\n7 5\n7 8\n";
+
+    fn clique(n: Vertex) -> StaticGraph {
+        let edges: Vec<(Vertex, Vertex)> = (0..n).flat_map(|u| ((u + 1)..n).map(move |v| (u, v))).collect();
+        StaticGraph::from_edges(n, edges)
+    }
+
+    fn check_agreement<V, G>(g: &G)
+    where
+        G: Graph<V> + Sync,
+        V: PrimInt + AsPrimitive<usize> + Send + Sync,
+        usize: AsPrimitive<V>,
+    {
+        let (ntri, _) = triangles(g);
+        assert_eq!(threaded_triangles(g), ntri);
+        assert_eq!(threaded_triangles_csr(g), ntri);
+        let vtri: usize = threaded_vertex_triangles(g).iter().sum();
+        assert_eq!(vtri as u128, 3 * ntri);
+        for method in Intersection::ALL.iter() {
+            assert_eq!(threaded_triangles_with(g, *method), ntri);
+        }
+        let mut nenum = 0u128;
+        for_each_triangle(g, |_, _, _| nenum += 1);
+        assert_eq!(nenum, ntri);
+    }
+
+    #[test]
+    fn threaded_agrees_with_serial() {
+        let g = StaticGraph::from_edge_reader(EDGES.as_bytes()).unwrap();
+        assert_eq!(triangles(&g).0, 9);
+        check_agreement(&g);
+
+        let h = VecGraph::from_edge_reader(EDGES.as_bytes()).unwrap();
+        assert_eq!(triangles(&h).0, 9);
+        check_agreement(&h);
+    }
+
+    #[test]
+    fn intersections() {
+        let a = [1, 3, 4, 7, 9, 12, 20, 21, 40];
+        let b = [0, 3, 7, 8, 12, 21, 22, 50, 51, 52, 53];
+        for method in Intersection::ALL.iter() {
+            assert_eq!(intersect(*method, &a, &b), 4);
+            assert_eq!(intersect(*method, &b, &a), 4);
+            assert_eq!(intersect(*method, &a, &[]), 0);
+        }
+        assert_eq!(intersect_galloping(&[3, 40], &a), 2);
+        assert_eq!(intersect_galloping(&[0, 41], &a), 0);
+    }
+
+    #[test]
+    fn estimates() {
+        let g = StaticGraph::from_edge_reader(EDGES.as_bytes()).unwrap();
+        let e = doulion_triangles(&g, 1.0, 3, 42);
+        assert_eq!((e.estimate, e.lower, e.upper), (9.0, 9.0, 9.0));
+        let e = wedge_sampling_triangles(&g, 1000, 42);
+        assert!(e.lower <= e.estimate && e.estimate <= e.upper);
+        assert_eq!(e.estimate, wedge_sampling_triangles(&g, 1000, 42).estimate);
+
+        // every wedge of a clique is closed.
+        let e = wedge_sampling_triangles(&clique(6), 100, 7);
+        assert_eq!((e.estimate, e.lower, e.upper), (20.0, 20.0, 20.0));
+
+        // K30 has 4060 triangles.
+        let k30 = clique(30);
+        let e = doulion_triangles(&k30, 0.5, 200, 3);
+        assert!(e.lower <= 4060.0 && 4060.0 <= e.upper);
+        assert!(e.upper - e.lower < 0.2 * 4060.0);
+    }
+
+    #[test]
+    fn doulion_is_generic() {
+        let h = VecGraph::from_edge_reader(EDGES.as_bytes()).unwrap();
+        let e = doulion_triangles(&h, 1.0, 3, 42);
+        assert_eq!((e.estimate, e.lower, e.upper), (9.0, 9.0, 9.0));
+        // both graphs sparsify the same edges for the same seed.
+        let g = StaticGraph::from_edge_reader(EDGES.as_bytes()).unwrap();
+        for seed in 0..10 {
+            let (a, b) = (doulion_triangles(&g, 0.6, 20, seed), doulion_triangles(&h, 0.6, 20, seed));
+            assert_eq!((a.estimate, a.lower, a.upper), (b.estimate, b.lower, b.upper));
+        }
+    }
+
+    #[test]
+    fn threaded_agrees_with_serial_on_cliques() {
+        // K_n has n choose 3 triangles.
+        for n in 3..12u32 {
+            let g = clique(n);
+            assert_eq!(triangles(&g).0, (n * (n - 1) * (n - 2) / 6) as u128);
+            check_agreement(&g);
+        }
+    }
+
+    // for each vertex, the number of pairs of its neighbors that are adjacent.
+    fn brute_force_vertex_triangles(g: &StaticGraph) -> Vec<usize> {
+        g.vertices().map(|u| {
+            let nbrs: Vec<Vertex> = g.out_neighbors(u).iter().cloned().filter(|v| *v != u).collect();
+            let mut ntri = 0;
+            for (i, v) in nbrs.iter().enumerate() {
+                ntri += nbrs[(i + 1)..].iter().filter(|w| g.out_neighbors(*v).binary_search(w).is_ok()).count();
+            }
+            ntri
+        }).collect()
+    }
+
+    #[test]
+    fn vertex_triangles() {
+        let mut graphs = vec![StaticGraph::from_edge_reader(EDGES.as_bytes()).unwrap(), StaticGraph::from_edges(0, vec![])];
+        graphs.extend((3..8).map(clique));
+        graphs.extend((0..10).map(|seed| random_graph(100, 200 + 100 * seed as usize, seed)));
+        for g in graphs.iter() {
+            let expected = brute_force_vertex_triangles(g);
+            assert_eq!(threaded_vertex_triangles(g), expected);
+            assert_eq!(3 * threaded_triangles(g), expected.iter().sum::<usize>() as u128);
+        }
+        let g = StaticGraph::from_edge_reader(EDGES.as_bytes()).unwrap();
+        assert_eq!(threaded_vertex_triangles(&g), vec![3, 3, 6, 6, 3, 4, 1, 1, 0]);
+    }
+
+    #[test]
+    fn clustering_coefficients() {
+        // K4 with a pendant vertex 4, an isolated vertex 5 and a vertex 6 with
+        // only a self-loop.
+        let g = StaticGraph::from_edges(7, vec![(0, 1), (0, 2), (0, 3), (1, 2), (1, 3), (2, 3), (3, 4), (6, 6)]);
+        assert_eq!(threaded_vertex_triangles(&g), vec![3, 3, 3, 3, 0, 0, 0]);
+        assert_eq!(local_clustering_coefficients(&g), vec![1.0, 1.0, 1.0, 0.5, 0.0, 0.0, 0.0]);
+        // 4 triangles over 3 + 3 + 3 + 6 wedges.
+        assert!((transitivity(&g) - 0.8).abs() < 1e-12);
+
+        let path = StaticGraph::from_edges(4, vec![(0, 1), (1, 2), (2, 3)]);
+        assert_eq!(local_clustering_coefficients(&path), vec![0.0; 4]);
+        assert_eq!(transitivity(&path), 0.0);
+
+        let triangle = clique(3);
+        assert_eq!(local_clustering_coefficients(&triangle), vec![1.0; 3]);
+        assert_eq!(transitivity(&triangle), 1.0);
+
+        let empty = StaticGraph::from_edges(3, vec![]);
+        assert_eq!(local_clustering_coefficients(&empty), vec![0.0; 3]);
+        assert_eq!(transitivity(&empty), 0.0);
+    }
+
+    // every triangle as a sorted triple, in lexicographic order.
+    fn brute_force_triangles(g: &StaticGraph) -> Vec<(Vertex, Vertex, Vertex)> {
+        let mut tris = Vec::new();
+        for u in g.vertices() {
+            for &v in g.out_neighbors(u).iter().filter(|v| **v > u) {
+                for &w in g.out_neighbors(v).iter().filter(|w| **w > v) {
+                    if g.has_edge(u, w) {
+                        tris.push((u, v, w));
+                    }
+                }
+            }
+        }
+        tris
+    }
+
+    fn check_enumeration(g: &StaticGraph) {
+        let expected = brute_force_triangles(g);
+        let mut serial = Vec::new();
+        for_each_triangle(g, |u, v, w| serial.push((u, v, w)));
+        let mut runs = vec![serial];
+        for nthreads in 1..5 {
+            let pool = rayon::ThreadPoolBuilder::new().num_threads(nthreads).build().unwrap();
+            let tris = Mutex::new(Vec::new());
+            pool.install(|| par_for_each_triangle(g, |u, v, w| tris.lock().unwrap().push((u, v, w))));
+            runs.push(tris.into_inner().unwrap());
+        }
+        for mut tris in runs {
+            assert!(tris.iter().all(|(u, v, w)| u < v && v < w));
+            tris.sort_unstable();
+            let n = tris.len();
+            tris.dedup();
+            assert_eq!(tris.len(), n);
+            assert_eq!(tris, expected);
+        }
+    }
+
+    #[test]
+    fn enumeration() {
+        check_enumeration(&StaticGraph::from_edge_reader(EDGES.as_bytes()).unwrap());
+        check_enumeration(&StaticGraph::from_edges(4, vec![(0, 1), (1, 2), (2, 0), (2, 2), (2, 3)]));
+        for n in 3..9 {
+            check_enumeration(&clique(n));
+        }
+        for seed in 0..10 {
+            check_enumeration(&random_graph(100, 300 + 150 * seed as usize, seed));
+        }
+    }
+}
@@ -0,0 +1,326 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+use rayon::prelude::*;
+
+use num::cast::AsPrimitive;
+use num::traits::PrimInt;
+use crate::traits::Graph;
+use crate::triangles::{for_each_triangle, par_for_each_triangle};
+
+// CSR index of the edge {u, v} stored in the row of its lower endpoint.
+fn edge_index<V>(g: &impl Graph<V>, offsets: &[usize], u: V, v: V) -> usize
+where
+    V: PrimInt + AsPrimitive<usize>,
+{
+    let (a, b) = if u < v { (u, v) } else { (v, u) };
+    let i = g.out_neighbors(a).binary_search(&b).expect("Invalid edge");
+    offsets[a.as_()] + i
+}
+
+// copies the value of every edge (u, v) with u < v to its reverse (v, u).
+// Self-loops get zero.
+fn mirror<V>(g: &impl Graph<V>, offsets: &[usize], values: &mut [usize])
+where
+    V: PrimInt + AsPrimitive<usize>,
+{
+    for u in g.vertices() {
+        let uu = u.as_();
+        for (i, v) in g.out_neighbors(u).iter().enumerate() {
+            if *v < u {
+                values[offsets[uu] + i] = values[edge_index(g, offsets, *v, u)];
+            } else if *v == u {
+                values[offsets[uu] + i] = 0;
+            }
+        }
+    }
+}
+
+// calls f(w) for every common neighbor w of u and v.
+fn for_each_common_neighbor<V, F>(g: &impl Graph<V>, u: V, v: V, mut f: F)
+where
+    V: PrimInt,
+    F: FnMut(V),
+{
+    let (a, b) = (g.out_neighbors(u), g.out_neighbors(v));
+    let (mut i, mut j) = (0, 0);
+    while i < a.len() && j < b.len() {
+        if a[i] < b[j] {
+            i += 1;
+        } else if a[i] > b[j] {
+            j += 1;
+        } else {
+            if a[i] != u && a[i] != v {
+                f(a[i]);
+            }
+            i += 1;
+            j += 1;
+        }
+    }
+}
+
+/// Trussness of every edge of an undirected graph: the largest k such that the
+/// edge belongs to the k-truss, in which every edge is in at least k - 2
+/// triangles. Values are in CSR edge order (see `Graph::edge_offsets`);
+/// self-loops have trussness zero.
+pub fn truss_decomposition<V>(g: &impl Graph<V>) -> Vec<usize>
+where
+    V: PrimInt + AsPrimitive<usize>,
+{
+    let offsets = g.edge_offsets();
+    let ne = g.ne();
+    let mut support = vec![0usize; ne];
+    for_each_triangle(g, |u, v, w| {
+        support[edge_index(g, &offsets, u, v)] += 1;
+        support[edge_index(g, &offsets, u, w)] += 1;
+        support[edge_index(g, &offsets, v, w)] += 1;
+    });
+
+    // (u, v, CSR index) for every edge with u < v.
+    let mut edges: Vec<(V, V, usize)> = Vec::new();
+    for u in g.vertices() {
+        let uu = u.as_();
+        for (i, v) in g.out_neighbors(u).iter().enumerate() {
+            if *v > u {
+                edges.push((u, *v, offsets[uu] + i));
+            }
+        }
+    }
+    let m = edges.len();
+    let maxsup = edges.iter().map(|e| support[e.2]).max().unwrap_or(0);
+
+    // bucket-sort edges by support, as in the k-core decomposition.
+    let mut bins = vec![0usize; maxsup + 1];
+    for e in edges.iter() {
+        bins[support[e.2]] += 1;
+    }
+    let mut start = 0;
+    for b in bins.iter_mut() {
+        let count = *b;
+        *b = start;
+        start += count;
+    }
+    let mut pos = vec![0usize; ne];
+    let mut order: Vec<usize> = vec![0; m];
+    for (k, e) in edges.iter().enumerate() {
+        let s = support[e.2];
+        pos[e.2] = bins[s];
+        order[bins[s]] = k;
+        bins[s] += 1;
+    }
+    for s in (1..=maxsup).rev() {
+        bins[s] = bins[s - 1];
+    }
+    bins[0] = 0;
+
+    let mut removed = vec![false; ne];
+    let mut trussness = vec![0usize; ne];
+    for i in 0..m {
+        let (u, v, e) = edges[order[i]];
+        let s = support[e];
+        trussness[e] = s + 2;
+        let mut affected: Vec<usize> = Vec::new();
+        for_each_common_neighbor(g, u, v, |w| {
+            let euw = edge_index(g, &offsets, u, w);
+            let evw = edge_index(g, &offsets, v, w);
+            if !removed[euw] && !removed[evw] {
+                affected.push(euw);
+                affected.push(evw);
+            }
+        });
+        for f in affected {
+            let sf = support[f];
+            if sf > s {
+                // move f to the front of its bin, then shrink the bin.
+                let pf = pos[f];
+                let pg = bins[sf];
+                let other = edges[order[pg]].2;
+                if other != f {
+                    order.swap(pf, pg);
+                    pos[f] = pg;
+                    pos[other] = pf;
+                }
+                bins[sf] += 1;
+                support[f] -= 1;
+            }
+        }
+        removed[e] = true;
+    }
+    mirror(g, &offsets, &mut trussness);
+    trussness
+}
+
+// edge states during parallel peeling.
+const ALIVE: u8 = 0;
+const PEELING: u8 = 1;
+const REMOVED: u8 = 2;
+
+/// Parallel truss decomposition. Supports are counted with
+/// `par_for_each_triangle`, then edges are peeled level by level, all edges
+/// of the current level at once. Returns the same values as
+/// `truss_decomposition`.
+pub fn parallel_truss_decomposition<V, G>(g: &G) -> Vec<usize>
+where
+    G: Graph<V> + Sync,
+    V: PrimInt + AsPrimitive<usize> + Send + Sync,
+    usize: AsPrimitive<V>,
+{
+    let offsets = g.edge_offsets();
+    let ne = g.ne();
+    let support: Vec<AtomicUsize> = (0..ne).map(|_| AtomicUsize::new(0)).collect();
+    par_for_each_triangle(g, |u, v, w| {
+        support[edge_index(g, &offsets, u, v)].fetch_add(1, Ordering::Relaxed);
+        support[edge_index(g, &offsets, u, w)].fetch_add(1, Ordering::Relaxed);
+        support[edge_index(g, &offsets, v, w)].fetch_add(1, Ordering::Relaxed);
+    });
+
+    let edges: Vec<(V, V, usize)> = (0..g.nv().as_()).into_par_iter().flat_map(|uu| {
+        let u: V = uu.as_();
+        let off = offsets[uu];
+        g.out_neighbors(u).iter().enumerate()
+            .filter(|(_, v)| **v > u)
+            .map(|(i, v)| (u, *v, off + i))
+            .collect::<Vec<_>>()
+    }).collect();
+
+    // decrements the support of f unless it is already at level k. Returns
+    // true if f just reached level k.
+    let decrement = |f: usize, k: usize| -> bool {
+        let mut s = support[f].load(Ordering::Relaxed);
+        while s > k {
+            match support[f].compare_exchange(s, s - 1, Ordering::Relaxed, Ordering::Relaxed) {
+                Ok(_) => return s - 1 == k,
+                Err(cur) => s = cur,
+            }
+        }
+        false
+    };
+
+    let mut state = vec![ALIVE; ne];
+    let mut trussness = vec![0usize; ne];
+    let mut nremaining = edges.len();
+    let mut k = 0usize;
+    while nremaining > 0 {
+        let mut frontier: Vec<(V, V, usize)> = edges.par_iter()
+            .filter(|e| state[e.2] == ALIVE && support[e.2].load(Ordering::Relaxed) == k)
+            .cloned()
+            .collect();
+        while !frontier.is_empty() {
+            for (_, _, e) in frontier.iter() {
+                state[*e] = PEELING;
+                trussness[*e] = k + 2;
+            }
+            let state_ref = &state;
+            let next: Vec<(V, V, usize)> = frontier.par_iter().flat_map(|&(u, v, e)| {
+                let mut reached: Vec<(V, V, usize)> = Vec::new();
+                for_each_common_neighbor(g, u, v, |w| {
+                    let euw = edge_index(g, &offsets, u, w);
+                    let evw = edge_index(g, &offsets, v, w);
+                    let (suw, svw) = (state_ref[euw], state_ref[evw]);
+                    if suw == REMOVED || svw == REMOVED {
+                        return;
+                    }
+                    // a triangle with two peeling edges is handled by the
+                    // lower one; a triangle with three by none.
+                    let dec_uw = suw == ALIVE && (svw == ALIVE || e < evw);
+                    let dec_vw = svw == ALIVE && (suw == ALIVE || e < euw);
+                    if dec_uw && decrement(euw, k) {
+                        reached.push((u, w, euw));
+                    }
+                    if dec_vw && decrement(evw, k) {
+                        reached.push((v, w, evw));
+                    }
+                });
+                reached
+            }).collect();
+            for (_, _, e) in frontier.iter() {
+                state[*e] = REMOVED;
+            }
+            nremaining -= frontier.len();
+            frontier = next;
+        }
+        k += 1;
+    }
+    mirror(g, &offsets, &mut trussness);
+    trussness
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::random_graph;
+    use crate::{StaticGraph, Vertex};
+
+    // trussness of every edge by peeling the k-truss for increasing k.
+    fn brute_force_trussness(g: &StaticGraph) -> Vec<usize> {
+        let offsets = g.edge_offsets();
+        let mut alive: Vec<bool> = g.vertices()
+            .flat_map(|u| g.out_neighbors(u).iter().map(move |v| *v != u))
+            .collect();
+        let mut trussness = vec![0; g.ne()];
+        let has = |alive: &[bool], u: Vertex, v: Vertex| {
+            match g.out_neighbors(u).binary_search(&v) {
+                Ok(i) => alive[offsets[u as usize] + i],
+                Err(_) => false,
+            }
+        };
+        let mut k = 2;
+        while alive.iter().any(|a| *a) {
+            for (e, a) in alive.iter().enumerate() {
+                if *a {
+                    trussness[e] = k;
+                }
+            }
+            loop {
+                let mut weak: Vec<usize> = Vec::new();
+                for u in g.vertices() {
+                    for (i, v) in g.out_neighbors(u).iter().enumerate() {
+                        let e = offsets[u as usize] + i;
+                        if !alive[e] {
+                            continue;
+                        }
+                        let support = g.vertices()
+                            .filter(|w| *w != u && *w != *v && has(&alive, u, *w) && has(&alive, *v, *w))
+                            .count();
+                        // the (k + 1)-truss needs k - 1 triangles per edge.
+                        if support + 1 < k {
+                            weak.push(e);
+                        }
+                    }
+                }
+                if weak.is_empty() {
+                    break;
+                }
+                weak.iter().for_each(|e| alive[*e] = false);
+            }
+            k += 1;
+        }
+        trussness
+    }
+
+    #[test]
+    fn clique_with_pendant_edge() {
+        let mut edges: Vec<(Vertex, Vertex)> = (0..4).flat_map(|u| ((u + 1)..4).map(move |v| (u, v))).collect();
+        edges.extend(vec![(3, 4), (4, 4)]);
+        let g = StaticGraph::from_edges(5, edges);
+        let offsets = g.edge_offsets();
+        let trussness = truss_decomposition(&g);
+        for u in g.vertices() {
+            for (i, v) in g.out_neighbors(u).iter().enumerate() {
+                let expected = if u == *v { 0 } else if u == 4 || *v == 4 { 2 } else { 4 };
+                assert_eq!(trussness[offsets[u as usize] + i], expected);
+            }
+        }
+        assert_eq!(parallel_truss_decomposition(&g), trussness);
+    }
+
+    #[test]
+    fn parallel_agrees_with_serial() {
+        for seed in 0..10 {
+            let g = random_graph(30, 80 + 15 * seed as usize, seed);
+            let trussness = truss_decomposition(&g);
+            assert_eq!(trussness, brute_force_trussness(&g));
+            assert_eq!(parallel_truss_decomposition(&g), trussness);
+        }
+        let g = random_graph(2000, 30000, 7);
+        assert_eq!(parallel_truss_decomposition(&g), truss_decomposition(&g));
+    }
+}